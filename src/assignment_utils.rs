@@ -1,28 +1,51 @@
-use super::fileops_utils::lines_from_file;
+use super::date_utils::{
+    canonical_due_date, parse_due_date, parse_stored_due_date, valid_local_time, DUE_DATE_FORMAT,
+};
+use super::errors::RkmError;
+use super::fileops_utils::{lines_from_file, write_lines_to_file};
+use super::rand_utils::normalize;
+use super::recency_utils::RecencyBoost;
+use super::recurrence_utils::Recurrence;
+use super::selection_utils::SelectionContext;
+use super::urgency_utils::UrgencyModel;
 use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
 use std::path::Path;
-use std::process::exit;
 use std::str::FromStr;
 
 // THESE ARE ALL FUNCTIONS RELATED TO THE ASSIGNMENT
 // STRUCTURE
 //
 
+// the priorities a task can be given after its due date, lowest
+// first. ! and C, !! and B, !!! and A are the same priority
+pub const PRIORITY_TOKENS: [[&str; 2]; 3] = [["!", "C"], ["!!", "B"], ["!!!", "A"]];
+
 // Define 'Assignment' object
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct Assignment {
     pub name: String,
     pub tag: String,
-    pub due_time: String,
+    // None for open-ended tasks
+    pub due_time: Option<String>,
+    // one of PRIORITY_TOKENS, as written in the task file
+    #[serde(default)]
+    pub priority: Option<String>,
+    // pomodoros the task is expected to take, written as ~N
+    #[serde(default)]
+    pub estimate: Option<u32>,
+    // how often the task comes back once it is done
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
 }
 
 // when I print an Assignment object
 //  what happens?
 impl fmt::Display for Assignment {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "({}, {}, {})", self.name, self.tag, self.due_time)
+        write!(f, "({}, {}, {})", self.name, self.tag, self.due_label())
     }
 }
 
@@ -30,150 +53,473 @@ impl fmt::Display for Assignment {
 impl Assignment {
     // Turn the string due date associated with a task
     // to the DateTime object associated with the chrono function
-    // note that we always assume Local timezone.
-    pub fn convert_due_date(&self) -> DateTime<Local> {
-        let convert_due_date = Local.datetime_from_str(&self.due_time, "%Y-%m-%d %H:%M");
-        match convert_due_date {
-            Ok(convert_due_date) => convert_due_date,
-            _ => panic!("{}", &self.due_time),
+    // note that we always assume Local timezone. None if the
+    // task has no due date
+    pub fn convert_due_date(&self) -> Result<Option<DateTime<Local>>, RkmError> {
+        match &self.due_time {
+            Some(due_time) => parse_stored_due_date(due_time).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    // the due date as shown in the UI
+    pub fn due_label(&self) -> &str {
+        self.due_time.as_deref().unwrap_or("none")
+    }
+
+    // has the task a due date before the given time?
+    pub fn is_overdue(&self, now: DateTime<Local>) -> Result<bool, RkmError> {
+        Ok(self
+            .convert_due_date()?
+            .map_or(false, |due_date| find_timeuntildue(due_date, now) < 0))
+    }
+
+    // 0 without a priority, up to 3 for !!! or A
+    pub fn priority_level(&self) -> i32 {
+        self.priority.as_ref().map_or(0, |priority| {
+            PRIORITY_TOKENS
+                .iter()
+                .position(|tokens| tokens.contains(&priority.as_str()))
+                .map_or(0, |level| level as i32 + 1)
+        })
+    }
+
+    // the optional fields after the due date
+    pub fn extras(&self) -> Vec<String> {
+        let mut extras: Vec<String> = self.priority.iter().cloned().collect();
+        extras.extend(self.estimate.map(|estimate| format!("~{}", estimate)));
+        extras.extend(self.recurrence.as_ref().map(|rule| rule.to_string()));
+        extras
+    }
+
+    // Is this the task as written in the task file? A repeating task
+    // read in after its due date has its due date rolled forward,
+    // so that one also matches the line it was read from
+    pub fn is_read_from(&self, line_assign: &Assignment) -> bool {
+        if self == line_assign {
+            return true;
         }
+        line_assign.recurrence.is_some()
+            && *self
+                == Assignment {
+                    due_time: self.due_time.clone(),
+                    ..line_assign.clone()
+                }
+            && matches!(
+                (line_assign.convert_due_date(), self.convert_due_date()),
+                (Ok(Some(line_due)), Ok(Some(due))) if line_due < due
+            )
+    }
+
+    // The task as it comes back after being done, with its due
+    // date rolled forward past now. None if it does not repeat
+    pub fn next_occurrence(&self, now: DateTime<Local>) -> Result<Option<Assignment>, RkmError> {
+        let rule = match &self.recurrence {
+            Some(rule) => rule,
+            None => return Ok(None),
+        };
+        // repeat rules are only read in with a due date
+        let due = match self.convert_due_date()? {
+            Some(due) => rule.next_due(due.naive_local(), now.naive_local()),
+            None => return Ok(None),
+        };
+        // the due date may fall in an hour skipped or repeated by a
+        // daylight saving change, which the task file would not take
+        Ok(Some(Assignment {
+            due_time: Some(canonical_due_date(valid_local_time(due))),
+            ..self.clone()
+        }))
+    }
+
+    // pomodoros done on the task, out of the estimate if it has one
+    pub fn progress(&self, done: i64) -> String {
+        match self.estimate {
+            Some(estimate) => format!("{}/{}", done, estimate),
+            None => done.to_string(),
+        }
+    }
+
+    // the line this assignment is written as in the task file.
+    // without a due date the field is left empty, or out if
+    // nothing comes after it
+    pub fn to_line(&self) -> String {
+        let mut fields = vec![self.tag.clone(), self.name.clone()];
+        let extras = self.extras();
+        if self.due_time.is_some() || !extras.is_empty() {
+            fields.push(self.due_time.clone().unwrap_or_default());
+        }
+        fields.extend(extras);
+        fields.join(", ")
     }
 }
 
 impl FromStr for Assignment {
-    type Err = &'static str;
+    type Err = RkmError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let task_vec: Vec<&str> = s.split(',').collect();
 
-        // ignore all lines in todo list that do not have 3
-        // fields or that start with '#'
-        if task_vec.len() != 3 || task_vec[0].starts_with('#') {
-            return Err("invalid!");
+        // ignore all lines in todo list that do not have at
+        // least 2 fields or that start with '#'
+        if task_vec.len() < 2 || task_vec[0].starts_with('#') {
+            return Err(RkmError::WrongFieldCount(task_vec.len()));
         }
         let tag = task_vec[0].trim();
         let name = task_vec[1].trim();
-        let due_date = task_vec[2].trim();
+        // a missing or empty due date makes an open-ended task
+        let due_date = task_vec
+            .get(2)
+            .map(|due_date| due_date.trim())
+            .filter(|due_date| !due_date.is_empty());
+
+        // anything after the due date is optional
+        let mut priority = None;
+        let mut estimate = None;
+        let mut recurrence = None;
+        for field in task_vec.iter().skip(3).map(|field| field.trim()) {
+            if field.is_empty() {
+                continue;
+            }
+            if PRIORITY_TOKENS.iter().any(|tokens| tokens.contains(&field)) {
+                priority = Some(field.to_string());
+            } else if field.starts_with('@') {
+                recurrence = Some(Recurrence::from_str(field)?);
+            } else if let Some(pomodoros) = field.strip_prefix('~') {
+                match pomodoros.parse::<u32>() {
+                    Ok(pomodoros) if pomodoros > 0 => estimate = Some(pomodoros),
+                    _ => return Err(RkmError::UnknownTaskField(field.to_string())),
+                }
+            } else {
+                return Err(RkmError::UnknownTaskField(field.to_string()));
+            }
+        }
+        // the next due date is counted from the last one
+        if let (Some(rule), None) = (&recurrence, due_date) {
+            return Err(RkmError::BadRecurrence {
+                rule: rule.to_string(),
+                reason: String::from("needs a due date to repeat from"),
+            });
+        }
         Ok(Self {
             name: String::from(name),
             tag: String::from(tag),
-            due_time: String::from(due_date),
+            due_time: due_date.map(String::from),
+            priority,
+            estimate,
+            recurrence,
         })
     }
 }
 
-// Take all minutes until due from all assignments. Find the
-// largest value. Divide that value by all values.
-// Sum these values. Use that as the denominator for all
-// values. Return this probability distribution.
-fn turn_timetilldue_into_pdf(due: Vec<i64>) -> Vec<f64> {
-    let biggest = due.iter().max().copied().unwrap_or_default();
-
-    let mut pdf: Vec<f64> = Vec::with_capacity(due.len());
-    let mut sum = 0_f64;
-
-    for i in due {
-        let value = biggest as f64 / i as f64;
-        pdf.push(value);
-        sum += value;
-    }
-
-    for prob in &mut pdf {
-        *prob /= sum;
-    }
-    pdf
-}
-
-// Get the amount of time until a given assignment is due in minutes
-pub fn find_timeuntildue(due_date: DateTime<Local>) -> i64 {
-    due_date.signed_duration_since(Local::now()).num_minutes()
+// Get the amount of time from now until a given assignment
+// is due in minutes
+pub fn find_timeuntildue(due_date: DateTime<Local>, now: DateTime<Local>) -> i64 {
+    due_date.signed_duration_since(now).num_minutes()
 }
 
 // Turn a vector containing all assignments, and return a Vec<f64>
 // that is your probability density function for each assignment
-// the index tracks the same assignment
-// pub fn turn_assignmentvector_into_pdf(assign: &Vec<Assignment>, use_due:
-// bool) -> Vec<f64> {
-pub fn turn_assignmentvector_into_pdf(assign: &[Assignment], use_due: bool) -> Vec<f64> {
-    if use_due {
-        let min_till_due = assign
-            .iter()
-            .map(|item| find_timeuntildue(item.convert_due_date()))
-            .collect();
-        turn_timetilldue_into_pdf(min_till_due)
+// the index tracks the same assignment. the urgency model decides
+// how much more likely tasks that are due sooner are, the recency
+// boost how much more likely tasks not worked on in a while are.
+// each level of priority makes a task priority_boost times more
+// likely, so 1 leaves priorities out. a task without a due date is
+// undated_weight times as likely as a dated task of average urgency
+pub fn turn_assignmentvector_into_pdf(
+    assign: &[Assignment],
+    use_due: bool,
+    urgency: &UrgencyModel,
+    recency: &RecencyBoost,
+    now: DateTime<Local>,
+    priority_boost: f64,
+    undated_weight: f64,
+) -> Result<Vec<f64>, RkmError> {
+    let mut dated = vec![];
+    for (idx, item) in assign.iter().enumerate() {
+        if let Some(due_date) = item.convert_due_date()? {
+            dated.push((idx, find_timeuntildue(due_date, now)));
+        }
+    }
+    let pdf = if use_due && !dated.is_empty() {
+        // undated tasks are left out of the urgency model so they
+        // do not change how the dated ones are weighted
+        let min_till_due: Vec<i64> = dated.iter().map(|(_, minutes)| *minutes).collect();
+        let mut pdf = vec![undated_weight / dated.len() as f64; assign.len()];
+        for ((idx, _), prob) in dated.iter().zip(urgency.pdf(&min_till_due)) {
+            pdf[*idx] = prob;
+        }
+        pdf
     } else {
         let uniform_prob: f64 = 1.0 / assign.len() as f64;
-        return vec![uniform_prob; assign.len()];
-    }
+        vec![uniform_prob; assign.len()]
+    };
+    let prioritized = pdf
+        .iter()
+        .zip(assign)
+        .map(|(prob, item)| prob * priority_boost.powi(item.priority_level()))
+        .collect();
+    Ok(recency.boost_task_pdf(normalize(prioritized), assign))
 }
 
 // Read in the tasks from the task file path and config tag list
 // Convert these into a hashmap linking each tag to a vector of
-// assignments associated with that tag
-pub fn readin_tasks(filepath: &Path, tag_list: &[String]) -> HashMap<String, Vec<Assignment>> {
-    let lines = lines_from_file(filepath);
+// assignments associated with that tag. tasks due before
+// `now` are left out
+pub fn readin_tasks(
+    filepath: &Path,
+    tag_list: &[String],
+    now: DateTime<Local>,
+) -> Result<HashMap<String, Vec<Assignment>>, RkmError> {
+    let lines = lines_from_file(filepath)?;
     let mut tag_to_taskvectors: HashMap<_, _> = tag_list
         .iter()
         .map(|tags| (tags.to_string(), Vec::default()))
         .collect();
 
-    for new_assign in lines
-        .iter()
-        .filter_map(|line| Assignment::from_str(line).ok())
-        .filter(|new_assign| find_timeuntildue(new_assign.convert_due_date()) >= 0)
-    {
-        assert!(
-            tag_to_taskvectors.contains_key(&new_assign.tag),
-            "Tag shown in task list not described in config: {}",
-            new_assign.tag
-        );
+    for (line_idx, line) in lines.iter().enumerate() {
+        let in_task_file = |source| RkmError::TaskFile {
+            path: filepath.to_path_buf(),
+            line: line_idx + 1,
+            source: Box::new(source),
+        };
+        let new_assign = match Assignment::from_str(line) {
+            Ok(new_assign) => new_assign,
+            // a stray comma should not keep the rest from loading.
+            // check reports these lines
+            Err(RkmError::WrongFieldCount(_)) | Err(RkmError::UnknownTaskField(_)) => continue,
+            Err(e) => return Err(in_task_file(e)),
+        };
 
-        tag_to_taskvectors
-            .get_mut(&new_assign.tag)
-            .unwrap()
-            .push(new_assign);
+        // overdue tasks are ignored, repeating ones come back
+        // with their next due date
+        let new_assign = if new_assign.is_overdue(now).map_err(in_task_file)? {
+            match new_assign.next_occurrence(now).map_err(in_task_file)? {
+                Some(next) => next,
+                None => continue,
+            }
+        } else {
+            new_assign
+        };
+
+        match tag_to_taskvectors.get_mut(&new_assign.tag) {
+            Some(assign_vec) => assign_vec.push(new_assign),
+            None => return Err(in_task_file(RkmError::UnknownTag(new_assign.tag))),
+        }
     }
 
     if tag_to_taskvectors.iter().all(|tag| tag.1.is_empty()) {
-        eprintln!(
-            "The task list is empty, or all tasks in your list are overdue.\nFill the file {} \
-             with your tasks.",
-            filepath.display()
-        );
-        exit(1);
+        return Err(RkmError::EmptyTaskList(filepath.to_path_buf()));
     }
 
-    tag_to_taskvectors
+    Ok(tag_to_taskvectors)
+}
+
+// Go through the task file line by line and find every problem
+// at once. Returns the problems that keep rkm from running and
+// the lines rkm would silently ignore separately.
+pub fn task_problems(
+    filepath: &Path,
+    tag_list: &[String],
+) -> Result<(Vec<RkmError>, Vec<RkmError>), RkmError> {
+    let mut errors = vec![];
+    let mut ignored = vec![];
+    for (line_idx, line) in lines_from_file(filepath)?.iter().enumerate() {
+        let in_task_file = |source| RkmError::TaskFile {
+            path: filepath.to_path_buf(),
+            line: line_idx + 1,
+            source: Box::new(source),
+        };
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        let new_assign = match Assignment::from_str(line) {
+            Ok(new_assign) => new_assign,
+            Err(e @ RkmError::WrongFieldCount(_)) | Err(e @ RkmError::UnknownTaskField(_)) => {
+                ignored.push(in_task_file(e));
+                continue;
+            }
+            Err(e) => {
+                errors.push(in_task_file(e));
+                continue;
+            }
+        };
+
+        // same order as readin_tasks: overdue tasks are ignored
+        // before their tag is looked at, unless they repeat
+        match new_assign.is_overdue(Local::now()) {
+            Ok(true) if new_assign.recurrence.is_none() => {
+                ignored.push(in_task_file(RkmError::Overdue {
+                    due: new_assign.due_label().to_string(),
+                    task: new_assign.name,
+                }));
+                continue;
+            }
+            Ok(_) => {}
+            Err(e) => errors.push(in_task_file(e)),
+        }
+        if !tag_list.contains(&new_assign.tag) {
+            errors.push(in_task_file(RkmError::UnknownTag(new_assign.tag)));
+        }
+    }
+    Ok((errors, ignored))
 }
 
-// convert the hashmap to a vector of strings
+// Rewrite the first line of the task file matching the given
+// assignment. The closure gets the old line and returns the lines
+// to replace it with, none to remove it. Returns false if no
+// matching line was found (e.g. it was edited by hand).
+fn rewrite_assignment_line<F>(
+    filepath: &Path,
+    target: &Assignment,
+    rewrite: F,
+) -> Result<bool, RkmError>
+where
+    F: FnOnce(&str) -> Vec<String>,
+{
+    let mut lines = lines_from_file(filepath)?;
+    let target_idx = lines.iter().position(|line| {
+        Assignment::from_str(line).map_or(false, |line_assign| target.is_read_from(&line_assign))
+    });
+
+    match target_idx {
+        Some(idx) => {
+            let new_lines = rewrite(&lines[idx]);
+            lines.splice(idx..=idx, new_lines);
+            write_lines_to_file(filepath, &lines).map_err(RkmError::file(filepath))?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+// Mark a finished assignment as done in the task file. The
+// matching line is turned into a comment carrying the
+// completion timestamp, so it is never read in again. A
+// recurring assignment gets its next occurrence right below.
+pub fn mark_assignment_complete(filepath: &Path, finished: &Assignment) -> Result<bool, RkmError> {
+    let now = Local::now();
+    let timestamp = now.format("%Y-%m-%d %H:%M");
+    let next = finished.next_occurrence(now)?;
+    rewrite_assignment_line(filepath, finished, |line| {
+        // a repeating task that was rolled forward is done with
+        // the due date it was worked on with
+        let done_line = match Assignment::from_str(line) {
+            Ok(line_assign) if line_assign == *finished => line.to_string(),
+            _ => finished.to_line(),
+        };
+        let mut new_lines = vec![format!("# [done {}] {}", timestamp, done_line)];
+        new_lines.extend(next.map(|next| next.to_line()));
+        new_lines
+    })
+}
+
+// does the field look like one of the optional fields after
+// the due date rather than a due date?
+pub fn is_extra_field(field: &str) -> bool {
+    let field = field.trim();
+    PRIORITY_TOKENS.iter().any(|tokens| tokens.contains(&field))
+        || field.starts_with('~')
+        || field.starts_with('@')
+}
+
+// Build a task out of what was typed into the task form or given
+// to the 'add' subcommand. The due date can be in any form
+// parse_due_date takes and is stored in canonical form. extras
+// are the fields after the due date, comma separated
+pub fn assignment_from_input(
+    tag: &str,
+    name: &str,
+    due: &str,
+    extras: &str,
+    now: DateTime<Local>,
+) -> Result<Assignment, RkmError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(RkmError::BadValue {
+            key: "name",
+            reason: String::from("can not be empty"),
+        });
+    }
+    // fields in the task file are separated by commas
+    if name.contains(',') {
+        return Err(RkmError::BadValue {
+            key: "name",
+            reason: String::from("can not contain commas"),
+        });
+    }
+    let due = match due.trim() {
+        "" => String::new(),
+        due => canonical_due_date(parse_due_date(due, now)?),
+    };
+    // read back the way the task file will be
+    let assign = Assignment::from_str(&format!("{}, {}, {}, {}", tag, name, due, extras))?;
+    if assign.is_overdue(now)? {
+        return Err(RkmError::BadValue {
+            key: "due date",
+            reason: String::from("is in the past"),
+        });
+    }
+    Ok(assign)
+}
+
+// Add a new assignment to the end of the task file
+pub fn append_assignment(filepath: &Path, new_assign: &Assignment) -> Result<(), RkmError> {
+    let mut lines = lines_from_file(filepath)?;
+    lines.push(new_assign.to_line());
+    write_lines_to_file(filepath, &lines).map_err(RkmError::file(filepath))
+}
+
+// Replace an assignment in the task file with an edited version
+pub fn replace_assignment(
+    filepath: &Path,
+    old_assign: &Assignment,
+    new_assign: &Assignment,
+) -> Result<bool, RkmError> {
+    rewrite_assignment_line(filepath, old_assign, |_| vec![new_assign.to_line()])
+}
+
+// Remove an assignment from the task file
+pub fn delete_assignment(filepath: &Path, old_assign: &Assignment) -> Result<bool, RkmError> {
+    rewrite_assignment_line(filepath, old_assign, |_| vec![])
+}
+
+// convert the hashmap to a flat vector of assignments
+// ordered by tag the way the UI lists them
 pub fn hashmap_to_taskvector(
     tagmap: &HashMap<String, Vec<Assignment>>,
     tag_vector: &[String],
-) -> Vec<Vec<String>> {
+) -> Vec<Assignment> {
     tag_vector
         .iter()
         .flat_map(|tags| tagmap.get(tags).unwrap())
         .cloned()
-        .map(|item| vec![item.tag, item.name, item.due_time])
         .collect()
 }
 
+// the rows of the stats page: every task with its tag
+// weight, its weight within the tag and the two combined
 pub fn create_weighttable(
     tagmap: &HashMap<String, Vec<Assignment>>,
-    tag_vector: &[String],
-    tag_weights: &[f64],
-    use_dues: &[bool],
-) -> Vec<Vec<String>> {
+    ctx: &SelectionContext,
+) -> Result<Vec<Vec<String>>, RkmError> {
     let mut toret = vec![];
-    for (i_tags, tags) in tag_vector.iter().enumerate() {
-        let tag_weight = tag_weights[i_tags];
+    for (i_tags, tags) in ctx.tags.iter().enumerate() {
+        let tag_weight = ctx.tag_weights[i_tags];
         let assign_vec = tagmap.get(tags).unwrap();
-        let assign_pdf = turn_assignmentvector_into_pdf(assign_vec, use_dues[i_tags]);
+        let assign_pdf = turn_assignmentvector_into_pdf(
+            assign_vec,
+            ctx.use_due_dates[i_tags],
+            &ctx.urgency[i_tags],
+            ctx.recency,
+            ctx.now,
+            ctx.priority_boosts[i_tags],
+            ctx.undated_weight,
+        )?;
         for (i, curr_assign) in assign_vec.iter().enumerate() {
             let mut new = vec![];
             new.push(curr_assign.tag.clone());
             new.push(curr_assign.name.clone());
+            new.push(curr_assign.priority.clone().unwrap_or_default());
             new.push(format!("{:.2}%", tag_weight * 100.0));
             new.push(format!("{:.2}%", assign_pdf[i] * 100.0));
             new.push(format!("{:.2}%", (assign_pdf[i] * tag_weight * 100.0)));
@@ -182,41 +528,82 @@ pub fn create_weighttable(
     }
     // following code to sort by percentage values
     toret.sort_by(|a, b| {
-        b[4][..b[4].find('%').unwrap()]
+        b[5][..b[5].find('%').unwrap()]
             .parse::<f32>()
             .unwrap()
-            .partial_cmp(&a[4][..a[4].find('%').unwrap()].parse::<f32>().unwrap())
+            .partial_cmp(&a[5][..a[5].find('%').unwrap()].parse::<f32>().unwrap())
             .unwrap()
     });
-    toret
+    Ok(toret)
 }
 
-// convert a given assigment to a string vector with newline characters
-pub fn taskvector_to_stringvect(curr_assign: &Assignment) -> Vec<String> {
-    let mut toret: Vec<String> = Vec::with_capacity(3);
+// convert a given assigment to a string vector with newline characters.
+// done is the number of pomodoros already spent on it
+pub fn taskvector_to_stringvect(curr_assign: &Assignment, done: i64) -> Vec<String> {
+    let mut toret: Vec<String> = Vec::with_capacity(4);
     let newline = "\n";
     let mut name = curr_assign.name.clone();
     name.push_str(newline);
     let mut tag = curr_assign.tag.clone();
     tag.push_str(newline);
-    let mut due_date = curr_assign.due_time.clone();
-    due_date.push_str(newline);
+    let due_date = format!(
+        "{}{}",
+        curr_assign.due_time.as_deref().unwrap_or("no due date"),
+        newline
+    );
     toret.push(tag);
     toret.push(name);
     toret.push(due_date);
+    toret.push(format!(
+        "pomodoros: {}{}",
+        curr_assign.progress(done),
+        newline
+    ));
+    if let Some(rule) = &curr_assign.recurrence {
+        toret.push(format!("repeats: {}{}", rule.describe(), newline));
+    }
     toret
 }
 
+// a task marked as done with 'c' and when that was
+pub struct DoneTask {
+    pub completed: DateTime<Local>,
+    pub task: Assignment,
+}
+
+// Read every task of the task file, overdue ones included, along
+// with the ones marked as done with 'c'. Lines that do not parse
+// are left out. Returns the open tasks and the done tasks.
+pub fn readin_all_tasks(filepath: &Path) -> Result<(Vec<Assignment>, Vec<DoneTask>), RkmError> {
+    let mut open = vec![];
+    let mut done = vec![];
+    for line in lines_from_file(filepath)? {
+        let done_line = line
+            .strip_prefix("# [done ")
+            .and_then(|line| line.split_once("] "));
+        match done_line {
+            Some((stamp, task_line)) => {
+                let completed = Local.datetime_from_str(stamp, DUE_DATE_FORMAT);
+                if let (Ok(completed), Ok(task)) = (completed, Assignment::from_str(task_line)) {
+                    done.push(DoneTask { completed, task });
+                }
+            }
+            None => open.extend(Assignment::from_str(&line).ok()),
+        }
+    }
+    Ok((open, done))
+}
+
 // Convert the vector of tags from the config file to a hashmap
 // linking each tag to an integer counter
-pub fn get_tag_counter_hashmap(tag_vector: &[String]) -> HashMap<&String, i64> {
-    tag_vector.iter().map(|tag| (tag, 0)).collect()
+pub fn get_tag_counter_hashmap(tag_vector: &[String]) -> HashMap<String, i64> {
+    tag_vector.iter().map(|tag| (tag.clone(), 0)).collect()
 }
 
 // Convert the task hashmap counter to a vector of string tuples
 // to be displayed.
 pub fn convert_hashmap_to_tuplevector(
-    x: &HashMap<&String, i64>,
+    x: &HashMap<String, i64>,
     tag: &[String],
 ) -> Vec<(String, String)> {
     let mut toret: Vec<(String, String)> = Vec::new();
@@ -246,6 +633,19 @@ pub fn update_tagweights(
             ctr += 1;
         }
     }
+    // every tag with weight ran out of tasks. the tags that
+    // still have tasks get an equal share instead
+    if ctr == 0 {
+        let with_tasks: Vec<bool> = vector_of_tags
+            .iter()
+            .map(|tag| tag_to_vector_map.get(tag).map_or(false, |v| !v.is_empty()))
+            .collect();
+        let share = 1.0 / with_tasks.iter().filter(|has| **has).count().max(1) as f64;
+        return with_tasks
+            .iter()
+            .map(|has| if *has { share } else { 0.0 })
+            .collect();
+    }
     let to_add = xi / f64::from(ctr);
     for item in updated_tag_weights.iter_mut().take(vector_of_tags.len()) {
         if *item != 0.0 {
@@ -254,3 +654,79 @@ pub fn update_tagweights(
     }
     updated_tag_weights.clone()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the line is read, written back and read again. the line
+    // written must read as the same task
+    fn round_trip(line: &str) -> (Assignment, String) {
+        let assign = Assignment::from_str(line).unwrap();
+        let written = assign.to_line();
+        assert!(Assignment::from_str(&written).unwrap() == assign);
+        (assign, written)
+    }
+
+    #[test]
+    fn two_field_task_has_no_due_date() {
+        let (assign, written) = round_trip("other, learn to juggle");
+        assert_eq!(assign.tag, "other");
+        assert_eq!(assign.name, "learn to juggle");
+        assert_eq!(assign.due_time, None);
+        assert_eq!(assign.due_label(), "none");
+        assert_eq!(written, "other, learn to juggle");
+    }
+
+    #[test]
+    fn three_field_task_keeps_its_due_date() {
+        let (assign, written) = round_trip("school,  Problem Set ,2050-10-12 10:00 ");
+        assert_eq!(assign.due_time.as_deref(), Some("2050-10-12 10:00"));
+        assert_eq!(written, "school, Problem Set, 2050-10-12 10:00");
+    }
+
+    #[test]
+    fn extra_fields_follow_the_due_date() {
+        let (assign, written) = round_trip("school, Make presentation, 2050-10-12 10:00, !!");
+        assert_eq!(assign.priority_level(), 2);
+        assert_eq!(written, "school, Make presentation, 2050-10-12 10:00, !!");
+
+        let (assign, written) =
+            round_trip("other, work on rust program, 2050-02-28 13:00, @weekly mon thu, ~4, A");
+        assert_eq!(assign.priority_level(), 3);
+        assert_eq!(assign.estimate, Some(4));
+        assert!(assign.recurrence.is_some());
+        // the extras are written in the same order every time
+        assert_eq!(
+            written,
+            "other, work on rust program, 2050-02-28 13:00, A, ~4, @weekly mon thu"
+        );
+    }
+
+    #[test]
+    fn empty_due_date_is_kept_before_extra_fields() {
+        let (assign, written) = round_trip("other, learn to juggle, , !");
+        assert_eq!(assign.due_time, None);
+        assert_eq!(assign.priority_level(), 1);
+        assert_eq!(written, "other, learn to juggle, , !");
+
+        // and left out when nothing follows it
+        let (assign, written) = round_trip("other, learn to juggle,  ");
+        assert_eq!(assign.due_time, None);
+        assert_eq!(written, "other, learn to juggle");
+    }
+
+    #[test]
+    fn malformed_lines_are_refused() {
+        assert!(matches!(
+            Assignment::from_str("just a name"),
+            Err(RkmError::WrongFieldCount(1))
+        ));
+        assert!(matches!(
+            Assignment::from_str("school, Problem Set, 2050-10-12 10:00, later"),
+            Err(RkmError::UnknownTaskField(_))
+        ));
+        // a repeating task needs a due date to repeat from
+        assert!(Assignment::from_str("other, water plants, , @daily").is_err());
+    }
+}
@@ -0,0 +1,325 @@
+use super::errors::{collect_problems, RkmError};
+use super::fileops_utils::lines_from_file;
+use chrono::prelude::*;
+use config::Value;
+use std::path::Path;
+
+// THESE ARE ALL FUNCTIONS RELATED TO TAG WEIGHTS
+// FOR PARTICULAR DATES
+//
+
+// One [[overrides]] block of the config: the tag weights to
+// use on every day from one date to another, both included
+#[derive(Clone)]
+pub struct DateOverride {
+    pub name: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub weights: Vec<f64>,
+}
+
+impl DateOverride {
+    pub fn covers(&self, date: NaiveDate) -> bool {
+        self.from <= date && date <= self.to
+    }
+
+    // e.g. "override exam week (2026-12-07 to 2026-12-11)"
+    pub fn describe(&self) -> String {
+        if self.from == self.to {
+            format!("override {} ({})", self.name, self.from)
+        } else {
+            format!("override {} ({} to {})", self.name, self.from, self.to)
+        }
+    }
+}
+
+// Read the [[overrides]] blocks of the config, with a problem for
+// every block that can not be read. Whether the weights fit the
+// tags is checked with the rest of the config.
+pub fn parse_overrides(blocks: Vec<Value>) -> Result<Vec<DateOverride>, Vec<RkmError>> {
+    collect_problems(
+        blocks
+            .into_iter()
+            .enumerate()
+            .map(|(block_idx, block)| parse_override(block_idx, block)),
+    )
+}
+
+// one [[overrides]] block, counted from 0
+fn parse_override(block_idx: usize, block: Value) -> Result<DateOverride, RkmError> {
+    let bad_override = |reason: String| RkmError::BadOverride {
+        block: block_idx + 1,
+        reason,
+    };
+    let mut params = block.into_table().map_err(|_| {
+        bad_override(String::from(
+            "expected a table like { from = \"2026-12-24\", weights = [...] }",
+        ))
+    })?;
+
+    let mut date = |key: &str| -> Result<Option<NaiveDate>, RkmError> {
+        match params.remove(key) {
+            None => Ok(None),
+            Some(value) => {
+                let value = value
+                    .into_string()
+                    .map_err(|_| bad_override(format!("{} is not a string", key)))?;
+                NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+                    .map(Some)
+                    .map_err(|_| {
+                        bad_override(format!("{} '{}' is not a date like 2026-12-24", key, value))
+                    })
+            }
+        }
+    };
+    let from = date("from")?.ok_or_else(|| bad_override(String::from("from is missing")))?;
+    // a single day unless the block says otherwise
+    let to = date("to")?.unwrap_or(from);
+    if to < from {
+        return Err(bad_override(String::from("to is before from")));
+    }
+
+    let name = match params.remove("name") {
+        Some(name) => name
+            .into_string()
+            .map_err(|_| bad_override(String::from("name is not a string")))?,
+        None => format!("{}", block_idx + 1),
+    };
+    let weights = params
+        .remove("weights")
+        .ok_or_else(|| bad_override(String::from("weights is missing")))?
+        .into_array()
+        .map_err(|_| bad_override(String::from("weights is not a list")))?
+        .into_iter()
+        .map(|weight| weight.into_float())
+        .collect::<Result<Vec<f64>, _>>()
+        .map_err(|_| bad_override(String::from("weights are not all numbers")))?;
+
+    Ok(DateOverride {
+        name,
+        from,
+        to,
+        weights,
+    })
+}
+
+// One event of the holiday calendar. Holidays last whole days
+#[derive(Clone)]
+pub struct Holiday {
+    pub name: String,
+    pub first: NaiveDate,
+    pub last: NaiveDate,
+    // RRULE:FREQ=YEARLY, e.g. christmas
+    pub yearly: bool,
+}
+
+impl Holiday {
+    pub fn covers(&self, date: NaiveDate) -> bool {
+        if !self.yearly {
+            return self.first <= date && date <= self.last;
+        }
+        // try the holiday as it falls this year and, for
+        // holidays spanning new year, the one of last year
+        let years_since = date.year() - self.first.year();
+        [years_since - 1, years_since]
+            .iter()
+            .filter(|years| **years >= 0)
+            .any(|years| {
+                let first = self.first.with_year(self.first.year() + years);
+                let last = self.last.with_year(self.last.year() + years);
+                match (first, last) {
+                    (Some(first), Some(last)) => first <= date && date <= last,
+                    // no feb 29 this year
+                    _ => false,
+                }
+            })
+    }
+}
+
+// a VEVENT of an .ics file as it is being read
+struct IcsEvent {
+    // where it starts in the file
+    line: usize,
+    summary: String,
+    start: Option<(NaiveDate, bool)>,
+    end: Option<(NaiveDate, bool)>,
+    yearly: bool,
+}
+
+// the date part of an iCalendar DTSTART or DTEND value, and
+// whether it came with a time of day
+fn ics_date(value: &str) -> Option<(NaiveDate, bool)> {
+    let date = NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok()?;
+    Some((date, value.len() > 8))
+}
+
+// Read the events of an .ics file, e.g. an export of public
+// holidays. Only what is needed to tell whether a day is a
+// holiday is read: start, end, summary and yearly repeats.
+pub fn read_holidays(path: &Path) -> Result<Vec<Holiday>, RkmError> {
+    let bad_calendar = |line: usize, reason: &str| RkmError::BadCalendar {
+        path: path.to_path_buf(),
+        line,
+        reason: reason.to_string(),
+    };
+
+    // long lines are folded onto lines starting with a space
+    let mut lines: Vec<(usize, String)> = vec![];
+    for (line_idx, line) in lines_from_file(path)?.into_iter().enumerate() {
+        match (
+            line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')),
+            lines.last_mut(),
+        ) {
+            (Some(rest), Some((_, last))) => last.push_str(rest),
+            _ => lines.push((line_idx + 1, line)),
+        }
+    }
+
+    let mut holidays = vec![];
+    let mut event: Option<IcsEvent> = None;
+    for (line_no, line) in lines {
+        let line = line.trim_end();
+        // properties look like NAME;PARAM=X:VALUE
+        let (property, value) = match line.split_once(':') {
+            Some(split) => split,
+            None => continue,
+        };
+        let name = property.split(';').next().unwrap_or_default();
+        match (name, event.as_mut()) {
+            ("BEGIN", None) if value == "VEVENT" => {
+                event = Some(IcsEvent {
+                    line: line_no,
+                    summary: String::from("holiday"),
+                    start: None,
+                    end: None,
+                    yearly: false,
+                });
+            }
+            ("DTSTART", Some(event)) => {
+                event.start = Some(
+                    ics_date(value)
+                        .ok_or_else(|| bad_calendar(line_no, "DTSTART is not a date"))?,
+                );
+            }
+            ("DTEND", Some(event)) => {
+                event.end = Some(
+                    ics_date(value).ok_or_else(|| bad_calendar(line_no, "DTEND is not a date"))?,
+                );
+            }
+            ("SUMMARY", Some(event)) => event.summary = value.to_string(),
+            ("RRULE", Some(event)) => {
+                event.yearly = value.split(';').any(|part| part == "FREQ=YEARLY");
+            }
+            ("END", Some(_)) if value == "VEVENT" => {
+                if let Some(done) = event.take() {
+                    let (first, _) = done
+                        .start
+                        .ok_or_else(|| bad_calendar(done.line, "event has no DTSTART"))?;
+                    // an all day DTEND is the day after the holiday
+                    let last = match done.end {
+                        Some((end, false)) => end.pred().max(first),
+                        Some((end, true)) => end.max(first),
+                        None => first,
+                    };
+                    holidays.push(Holiday {
+                        name: done.summary,
+                        first,
+                        last,
+                        yearly: done.yearly,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(holidays)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    // an .ics file of the given lines in the temp directory
+    fn calendar(name: &str, lines: &[&str]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("rkm-{}-{}.ics", name, std::process::id()));
+        fs::write(&path, lines.join("\r\n")).unwrap();
+        path
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn folded_lines_are_joined() {
+        let path = calendar(
+            "folded",
+            &[
+                "BEGIN:VCALENDAR",
+                "BEGIN:VEVENT",
+                "DTSTART;VALUE=DATE:20261225",
+                "SUMMARY:Christ",
+                " mas Day",
+                "RRULE:FREQ=YEARLY;",
+                "\tBYMONTH=12",
+                "END:VEVENT",
+                "END:VCALENDAR",
+            ],
+        );
+        let holidays = read_holidays(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(holidays.len(), 1);
+        assert_eq!(holidays[0].name, "Christmas Day");
+        assert!(holidays[0].yearly);
+        assert!(holidays[0].covers(date("2030-12-25")));
+        assert!(!holidays[0].covers(date("2030-12-26")));
+    }
+
+    #[test]
+    fn all_day_dtend_is_the_day_after() {
+        let path = calendar(
+            "dtend",
+            &[
+                "BEGIN:VCALENDAR",
+                "BEGIN:VEVENT",
+                "DTSTART;VALUE=DATE:20261224",
+                "DTEND;VALUE=DATE:20261227",
+                "SUMMARY:Winter break",
+                "END:VEVENT",
+                "BEGIN:VEVENT",
+                "DTSTART:20261231T090000",
+                "DTEND:20270101T120000",
+                "SUMMARY:New year",
+                "END:VEVENT",
+                "END:VCALENDAR",
+            ],
+        );
+        let holidays = read_holidays(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(holidays.len(), 2);
+        assert_eq!(
+            (holidays[0].first, holidays[0].last),
+            (date("2026-12-24"), date("2026-12-26"))
+        );
+        // a DTEND with a time of day is the last day itself
+        assert_eq!(
+            (holidays[1].first, holidays[1].last),
+            (date("2026-12-31"), date("2027-01-01"))
+        );
+    }
+
+    #[test]
+    fn events_without_a_start_are_refused() {
+        let path = calendar(
+            "nostart",
+            &["BEGIN:VEVENT", "SUMMARY:Someday", "END:VEVENT"],
+        );
+        let holidays = read_holidays(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(holidays.is_err());
+    }
+}
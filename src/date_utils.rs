@@ -0,0 +1,277 @@
+use super::errors::RkmError;
+use chrono::prelude::*;
+use chrono::{Duration, LocalResult};
+
+// THESE ARE ALL FUNCTIONS RELATED TO READING
+// DUE DATES
+//
+
+// how due dates are written to the task file
+pub const DUE_DATE_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+// what a day without a time of day is due at
+fn end_of_day() -> NaiveTime {
+    NaiveTime::from_hms(23, 59, 0)
+}
+
+fn bad_due_date(value: &str, reason: &str) -> RkmError {
+    RkmError::BadDueDate {
+        value: value.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+// a wall clock time in the local timezone
+fn local_time(value: &str, naive: NaiveDateTime) -> Result<DateTime<Local>, RkmError> {
+    single_time(value, Local.from_local_datetime(&naive))
+}
+
+// the one time a wall clock time stands for. times skipped or
+// repeated by a daylight saving change are refused
+fn single_time<T>(value: &str, time: LocalResult<T>) -> Result<T, RkmError> {
+    match time {
+        LocalResult::Single(due_date) => Ok(due_date),
+        LocalResult::Ambiguous(_, _) => Err(bad_due_date(
+            value,
+            "happens twice when the clocks go back. pick another time",
+        )),
+        LocalResult::None => Err(bad_due_date(
+            value,
+            "does not exist when the clocks go forward. pick another time",
+        )),
+    }
+}
+
+// The time a wall clock time is read as, looked up with the given
+// timezone rules. Where the clocks go back the first of the two is
+// taken, where they go forward the time moves on to the end of the
+// skipped stretch
+fn valid_time<T>(naive: NaiveDateTime, lookup: impl Fn(&NaiveDateTime) -> LocalResult<T>) -> T {
+    let mut time = naive;
+    loop {
+        match lookup(&time) {
+            LocalResult::Single(valid) => return valid,
+            LocalResult::Ambiguous(earliest, _) => return earliest,
+            LocalResult::None => time += Duration::minutes(1),
+        }
+    }
+}
+
+// a wall clock time computed rather than typed in (e.g. the next due
+// date of a repeating task) as a time that exists in the local timezone
+pub fn valid_local_time(naive: NaiveDateTime) -> DateTime<Local> {
+    valid_time(naive, |naive| Local.from_local_datetime(naive))
+}
+
+// the due date as written to the task file
+pub fn canonical_due_date(due_date: DateTime<Local>) -> String {
+    due_date.format(DUE_DATE_FORMAT).to_string()
+}
+
+// A due date that means the same whenever it is read: year-month-day
+// hour:minute, the same with a T and seconds, ISO 8601 with an offset
+// or a date alone meaning the end of that day. None if the value is
+// in none of these forms
+fn absolute_due_date(value: &str) -> Result<Option<DateTime<Local>>, RkmError> {
+    for format in &[
+        DUE_DATE_FORMAT,
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S",
+    ] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(value, format) {
+            return local_time(value, naive).map(Some);
+        }
+    }
+    for format in &["%Y-%m-%dT%H:%M%:z", "%Y-%m-%d %H:%M%:z"] {
+        if let Ok(due_date) = DateTime::parse_from_str(value, format) {
+            return Ok(Some(due_date.with_timezone(&Local)));
+        }
+    }
+    if let Ok(due_date) = DateTime::parse_from_rfc3339(value) {
+        return Ok(Some(due_date.with_timezone(&Local)));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return local_time(value, date.and_time(end_of_day())).map(Some);
+    }
+    Ok(None)
+}
+
+// Read a due date from the task file. Only forms that do not
+// depend on when they are read are taken there
+pub fn parse_stored_due_date(value: &str) -> Result<DateTime<Local>, RkmError> {
+    absolute_due_date(value)?.ok_or_else(|| {
+        bad_due_date(
+            value,
+            "expected year-month-day hour:minute, year-month-day or ISO 8601",
+        )
+    })
+}
+
+// the day a relative date like tomorrow, fri, +3d or next
+// week falls on, counted from today
+fn relative_day(value: &str, words: &str, today: NaiveDate) -> Result<NaiveDate, RkmError> {
+    let today_idx = today.weekday().num_days_from_monday() as i64;
+
+    if let Some(count) = words.strip_prefix('+') {
+        let (count, unit) = count.split_at(count.len().saturating_sub(1));
+        return match (count.parse::<i64>(), unit) {
+            (Ok(count), "d") if count >= 0 => Ok(today + Duration::days(count)),
+            (Ok(count), "w") if count >= 0 => Ok(today + Duration::weeks(count)),
+            _ => Err(bad_due_date(
+                value,
+                "expected a number of days or weeks like +3d or +2w",
+            )),
+        };
+    }
+    match words {
+        "today" => Ok(today),
+        "tomorrow" => Ok(today + Duration::days(1)),
+        // the monday starting next week
+        "next week" => Ok(today + Duration::days(7 - today_idx)),
+        // fri or friday
+        _ => match words.parse::<Weekday>() {
+            // fri on a friday could mean today or a week from now
+            Ok(day) if day == today.weekday() => {
+                Err(bad_due_date(value, "is today. write today or +7d instead"))
+            }
+            Ok(day) => Ok(today
+                + Duration::days((day.num_days_from_monday() as i64 - today_idx).rem_euclid(7))),
+            Err(_) => Err(bad_due_date(
+                value,
+                "expected a date like 2026-11-02 17:00, 2026-11-02, tomorrow 17:00, fri, \
+                 +3d or next week",
+            )),
+        },
+    }
+}
+
+// Read a due date typed in by hand. Besides the forms of the task
+// file this takes today, tomorrow, weekdays, +3d, +2w and next week,
+// each optionally followed by a time, and a time alone for today.
+// Without a time a date is due at the end of the day. Dates with
+// slashes are refused as the order of day and month is unclear.
+pub fn parse_due_date(value: &str, now: DateTime<Local>) -> Result<DateTime<Local>, RkmError> {
+    let value = value.trim();
+    if let Some(due_date) = absolute_due_date(value)? {
+        return Ok(due_date);
+    }
+    let lowered = value.to_lowercase();
+    if lowered.contains('/') {
+        return Err(bad_due_date(
+            value,
+            "could be day/month or month/day. write year-month-day instead",
+        ));
+    }
+
+    // a time of day can come last
+    let (words, time) = match lowered.rsplit_once(' ') {
+        Some((words, time)) => match NaiveTime::parse_from_str(time, "%H:%M") {
+            Ok(time) => (words.trim(), Some(time)),
+            Err(_) => (lowered.as_str(), None),
+        },
+        None => match NaiveTime::parse_from_str(&lowered, "%H:%M") {
+            Ok(time) => ("today", Some(time)),
+            Err(_) => (lowered.as_str(), None),
+        },
+    };
+    let day = relative_day(value, words, now.naive_local().date())?;
+    local_time(value, day.and_time(time.unwrap_or_else(end_of_day)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a wednesday
+    fn now() -> DateTime<Local> {
+        Local.ymd(2026, 10, 14).and_hms(10, 0, 0)
+    }
+
+    fn parsed(value: &str) -> String {
+        canonical_due_date(parse_due_date(value, now()).unwrap())
+    }
+
+    #[test]
+    fn relative_dates_count_from_today() {
+        assert_eq!(parsed("today"), "2026-10-14 23:59");
+        assert_eq!(parsed("Tomorrow 17:00"), "2026-10-15 17:00");
+        assert_eq!(parsed("fri"), "2026-10-16 23:59");
+        assert_eq!(parsed("monday 09:30"), "2026-10-19 09:30");
+        assert_eq!(parsed("+3d"), "2026-10-17 23:59");
+        assert_eq!(parsed("+2w 08:00"), "2026-10-28 08:00");
+        assert_eq!(parsed("next week"), "2026-10-19 23:59");
+        assert_eq!(parsed("12:30"), "2026-10-14 12:30");
+    }
+
+    #[test]
+    fn unclear_relative_dates_are_refused() {
+        for value in &["wed", "10/11", "+3x", "+-1d", "someday"] {
+            assert!(parse_due_date(value, now()).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn absolute_dates_in_every_form() {
+        assert_eq!(parsed("2026-11-02 17:00"), "2026-11-02 17:00");
+        assert_eq!(parsed("2026-11-02T17:00"), "2026-11-02 17:00");
+        assert_eq!(parsed("2026-11-02T17:00:00"), "2026-11-02 17:00");
+        assert_eq!(parsed("2026-11-02"), "2026-11-02 23:59");
+    }
+
+    #[test]
+    fn iso_dates_with_an_offset_keep_their_instant() {
+        let expected = Utc.ymd(2026, 11, 2).and_hms(16, 0, 0);
+        for value in &[
+            "2026-11-02T17:00+01:00",
+            "2026-11-02 17:00+01:00",
+            "2026-11-02T17:00:00+01:00",
+            "2026-11-02T16:00:00Z",
+        ] {
+            let due_date = parse_due_date(value, now()).unwrap();
+            assert_eq!(due_date.with_timezone(&Utc), expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn stored_due_dates_can_not_be_relative() {
+        assert!(parse_stored_due_date("2026-11-02 17:00").is_ok());
+        assert!(parse_stored_due_date("tomorrow").is_err());
+        assert!(parse_stored_due_date("+3d").is_err());
+    }
+
+    #[test]
+    fn computed_times_are_moved_out_of_daylight_saving_changes() {
+        // clocks go forward from 02:00 to 03:00 and back from 03:00 to 02:00
+        let at = |value: &str| NaiveDateTime::parse_from_str(value, DUE_DATE_FORMAT).unwrap();
+        let lookup = |naive: &NaiveDateTime| match (naive.month(), naive.hour()) {
+            (3, 2) => LocalResult::None,
+            (10, 2) => LocalResult::Ambiguous(*naive, *naive + Duration::hours(1)),
+            _ => LocalResult::Single(*naive),
+        };
+        assert_eq!(
+            valid_time(at("2026-03-29 02:30"), lookup),
+            at("2026-03-29 03:00")
+        );
+        assert_eq!(
+            valid_time(at("2026-10-25 02:30"), lookup),
+            at("2026-10-25 02:30")
+        );
+        assert_eq!(
+            valid_time(at("2026-03-29 09:00"), lookup),
+            at("2026-03-29 09:00")
+        );
+    }
+
+    #[test]
+    fn times_changed_by_daylight_saving_are_refused() {
+        let skipped: LocalResult<DateTime<Local>> = LocalResult::None;
+        assert!(single_time("2026-03-29 02:30", skipped).is_err());
+        let repeated = LocalResult::Ambiguous(now(), now() + Duration::hours(1));
+        assert!(single_time("2026-10-25 02:30", repeated).is_err());
+        assert_eq!(
+            single_time("2026-10-14 10:00", LocalResult::Single(now())).unwrap(),
+            now()
+        );
+    }
+}
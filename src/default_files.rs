@@ -1,3 +1,4 @@
+use super::errors::RkmError;
 use serde::Serialize;
 use std::fs::File;
 use std::io::Write;
@@ -8,8 +9,8 @@ use tinytemplate::TinyTemplate;
 // 1. Config      ($CONFIG/rusty-krab-manager/config.toml)
 // 2. "Ocean man" ($CONFIG/rusty-krab-manager/ocean_man.mp3)
 // 3. Tasks       ($HOME/rusty-krab-manager-tasks.txt)
-pub fn create() {
-    let mut config_subdir = dirs::config_dir().unwrap();
+pub fn create() -> Result<(), RkmError> {
+    let mut config_subdir = dirs::config_dir().unwrap_or_default();
     config_subdir.push("rusty-krab-manager");
 
     let config_filepath = config_subdir.join("config.toml");
@@ -23,7 +24,7 @@ pub fn create() {
             task_filepath: task_filepath.to_str().unwrap().to_string(),
         })
         .as_bytes(),
-    );
+    )?;
     create_default_file(
         &sound_filepath,
         include_bytes!(concat!(
@@ -33,7 +34,7 @@ pub fn create() {
             path_separator!(),
             "ocean_man.mp3"
         )),
-    );
+    )?;
     create_default_file(
         &task_filepath,
         include_bytes!(concat!(
@@ -43,16 +44,17 @@ pub fn create() {
             path_separator!(),
             "tasks"
         )),
-    );
+    )
 }
 
-fn create_default_file(filepath: &Path, contents: &[u8]) {
+fn create_default_file(filepath: &Path, contents: &[u8]) -> Result<(), RkmError> {
     if filepath.exists() {
-        return;
+        return Ok(());
     }
 
-    let mut file = File::create(filepath).unwrap();
-    file.write_all(contents).unwrap();
+    File::create(filepath)
+        .and_then(|mut file| file.write_all(contents))
+        .map_err(RkmError::file(filepath))
 }
 
 #[derive(Serialize)]
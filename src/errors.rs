@@ -0,0 +1,242 @@
+use super::assignment_utils::PRIORITY_TOKENS;
+use super::selection_utils::STRATEGY_NAMES;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::sync::mpsc;
+
+// Everything that can go wrong in rkm. Each variant carries
+// enough context (file, line, offending value) to tell the
+// user what to fix.
+#[derive(Debug)]
+pub enum RkmError {
+    // a file could not be read or written
+    File {
+        path: PathBuf,
+        source: io::Error,
+    },
+    // drawing to or reading from the terminal failed
+    Terminal(io::Error),
+    // the config file could not be parsed or a key is missing
+    Config(config::ConfigError),
+    // a path given in the config does not exist
+    MissingPath {
+        key: &'static str,
+        path: PathBuf,
+    },
+    // a per-tag array in the config has the wrong length
+    WrongLength {
+        key: String,
+        expected: usize,
+        found: usize,
+    },
+    // a weight array in the config does not sum to 1
+    WeightSum {
+        key: String,
+        sum: f64,
+    },
+    // a due date could not be parsed
+    BadDueDate {
+        value: String,
+        reason: String,
+    },
+    // a task uses a tag that is not in the config
+    UnknownTag(String),
+    // a line of the task file has fewer than 2 fields
+    WrongFieldCount(usize),
+    // a field after the due date is not a priority, estimate or
+    // repeat rule. the line is skipped like one with too few fields
+    UnknownTaskField(String),
+    // the @ rule of a recurring task is not valid
+    BadRecurrence {
+        rule: String,
+        reason: String,
+    },
+    // a task is past its due date
+    Overdue {
+        task: String,
+        due: String,
+    },
+    // a problem on a given line of the task file
+    TaskFile {
+        path: PathBuf,
+        line: usize,
+        source: Box<RkmError>,
+    },
+    // there is nothing left to roll
+    EmptyTaskList(PathBuf),
+    // selection_strategy in the config is not a known strategy
+    UnknownStrategy(String),
+    // a number in the config is out of range
+    BadValue {
+        key: &'static str,
+        reason: String,
+    },
+    // a tag's entry in the [quotas] table is not a valid quota
+    BadQuota {
+        tag: String,
+        reason: String,
+    },
+    // a tag's entry in the [urgency] table is not a valid model
+    BadUrgency {
+        tag: String,
+        reason: String,
+    },
+    // a [[schedule]] block is not valid, counted from 1
+    BadSchedule {
+        block: usize,
+        reason: String,
+    },
+    // an [[overrides]] block is not valid, counted from 1
+    BadOverride {
+        block: usize,
+        reason: String,
+    },
+    // the holiday calendar could not be read
+    BadCalendar {
+        path: PathBuf,
+        line: usize,
+        reason: String,
+    },
+    // weights to roll a die with do not add up to 1
+    BadDistribution(String),
+    // the sound could not be played
+    Audio(String),
+    // the desktop notification could not be shown
+    Notification(String),
+}
+
+impl fmt::Display for RkmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RkmError::File { path, source } => write!(f, "{}: {}", path.display(), source),
+            RkmError::Terminal(e) => write!(f, "terminal error: {}", e),
+            RkmError::Config(e) => write!(f, "config error: {}", e),
+            RkmError::MissingPath { key, path } => {
+                write!(f, "{} does not exist: {}", key, path.display())
+            }
+            RkmError::WrongLength {
+                key,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} has {} values but there are {} tags in the config",
+                key, found, expected
+            ),
+            RkmError::WeightSum { key, sum } => {
+                write!(f, "{} does not sum to 1. it sums to {}", key, sum)
+            }
+            RkmError::BadDueDate { value, reason } => {
+                write!(f, "due date '{}': {}", value, reason)
+            }
+            RkmError::UnknownTag(tag) => write!(f, "tag '{}' is not described in config", tag),
+            RkmError::WrongFieldCount(found) => write!(
+                f,
+                "line has {} fields instead of at least 2 (tag, description) and will \
+                 be ignored",
+                found
+            ),
+            RkmError::UnknownTaskField(field) => write!(
+                f,
+                "unknown field '{}' after the due date. expected a priority ({}), an \
+                 estimate like ~3 or a repeat rule like @weekly. the line will be ignored",
+                field,
+                PRIORITY_TOKENS
+                    .iter()
+                    .map(|tokens| tokens.join(" or "))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            RkmError::BadRecurrence { rule, reason } => {
+                write!(f, "repeat rule '{}': {}", rule, reason)
+            }
+            RkmError::Overdue { task, due } => {
+                write!(f, "'{}' was due {} and will be ignored", task, due)
+            }
+            RkmError::TaskFile { path, line, source } => {
+                write!(f, "{}:{}: {}", path.display(), line, source)
+            }
+            RkmError::UnknownStrategy(name) => write!(
+                f,
+                "unknown selection_strategy '{}'. expected one of: {}",
+                name,
+                STRATEGY_NAMES.join(", ")
+            ),
+            RkmError::BadValue { key, reason } => write!(f, "{} {}", key, reason),
+            RkmError::BadQuota { tag, reason } => write!(f, "quotas.{} {}", tag, reason),
+            RkmError::BadUrgency { tag, reason } => write!(f, "urgency.{}: {}", tag, reason),
+            RkmError::BadSchedule { block, reason } => {
+                write!(f, "schedule block {}: {}", block, reason)
+            }
+            RkmError::BadOverride { block, reason } => {
+                write!(f, "overrides block {}: {}", block, reason)
+            }
+            RkmError::BadCalendar { path, line, reason } => {
+                write!(f, "{}:{}: {}", path.display(), line, reason)
+            }
+            RkmError::EmptyTaskList(path) => write!(
+                f,
+                "The task list is empty, or all tasks in your list are overdue.\nFill the file {} \
+                 with your tasks.",
+                path.display()
+            ),
+            RkmError::Audio(e) => write!(f, "could not play sound: {}", e),
+            RkmError::BadDistribution(e) => write!(f, "can not roll: {}", e),
+            RkmError::Notification(e) => write!(f, "could not show notification: {}", e),
+        }
+    }
+}
+
+impl Error for RkmError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RkmError::File { source, .. } => Some(source),
+            RkmError::Terminal(e) => Some(e),
+            RkmError::Config(e) => Some(e),
+            RkmError::TaskFile { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl RkmError {
+    // attach the file a failed io operation was working on
+    pub fn file(path: impl Into<PathBuf>) -> impl FnOnce(io::Error) -> RkmError {
+        let path = path.into();
+        move |source| RkmError::File { path, source }
+    }
+}
+
+// The values of a list of config entries read one at a time, or
+// the problems with every entry that could not be read
+pub fn collect_problems<T>(
+    results: impl IntoIterator<Item = Result<T, RkmError>>,
+) -> Result<Vec<T>, Vec<RkmError>> {
+    let mut values = vec![];
+    let mut problems = vec![];
+    for result in results {
+        match result {
+            Ok(value) => values.push(value),
+            Err(e) => problems.push(e),
+        }
+    }
+    if problems.is_empty() {
+        Ok(values)
+    } else {
+        Err(problems)
+    }
+}
+
+impl From<mpsc::RecvError> for RkmError {
+    fn from(e: mpsc::RecvError) -> Self {
+        RkmError::Terminal(io::Error::new(io::ErrorKind::BrokenPipe, e))
+    }
+}
+
+impl From<config::ConfigError> for RkmError {
+    fn from(e: config::ConfigError) -> Self {
+        RkmError::Config(e)
+    }
+}
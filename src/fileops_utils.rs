@@ -1,15 +1,133 @@
+use super::errors::RkmError;
+use serde::Serialize;
 use std::{
-    fs::File,
-    io::{prelude::*, BufReader},
+    collections::hash_map::DefaultHasher,
+    fs::{self, File, OpenOptions},
+    hash::{Hash, Hasher},
+    io::{self, prelude::*, BufReader, BufWriter},
     path::Path,
 };
 
 // Read in a file and convert that into a vector of
 // strings to be parsed
-pub fn lines_from_file(filename: &Path) -> Vec<String> {
-    let file = File::open(filename).expect("no such file");
+pub fn lines_from_file(filename: &Path) -> Result<Vec<String>, RkmError> {
+    let file = File::open(filename).map_err(RkmError::file(filename))?;
     let buf = BufReader::new(file);
     buf.lines()
-        .map(|l| l.expect("Could not parse line"))
-        .collect()
+        .collect::<io::Result<Vec<String>>>()
+        .map_err(RkmError::file(filename))
+}
+
+// A fingerprint of what a file holds, to tell whether it changed
+// since it was last looked at. None if it can not be read
+pub fn file_fingerprint(filename: &Path) -> Option<u64> {
+    let mut hasher = DefaultHasher::new();
+    fs::read(filename).ok()?.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+// Write a vector of lines back to a file. The lines are first
+// written to a temporary file next to the original which is then
+// renamed over it, so a crash mid-write never leaves a truncated file.
+// A symlink is followed so the file it points to is replaced rather
+// than the link, and the file keeps its permissions
+pub fn write_lines_to_file(filename: &Path, lines: &[String]) -> io::Result<()> {
+    let (target, permissions) = match fs::canonicalize(filename) {
+        Ok(target) => {
+            let permissions = fs::metadata(&target)?.permissions();
+            (target, Some(permissions))
+        }
+        // not written yet
+        Err(e) if e.kind() == io::ErrorKind::NotFound => (filename.to_path_buf(), None),
+        Err(e) => return Err(e),
+    };
+    let mut tmp_name = target.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = Path::new(&tmp_name);
+
+    let mut buf = BufWriter::new(File::create(tmp_path)?);
+    for line in lines {
+        writeln!(buf, "{}", line)?;
+    }
+    let file = buf.into_inner()?;
+    if let Some(permissions) = permissions {
+        file.set_permissions(permissions)?;
+    }
+    file.sync_all()?;
+    fs::rename(tmp_path, &target)
+}
+
+// Append one value as a line of JSON to a log file, creating
+// the file and its directory if needed
+pub fn append_json_line<T: Serialize>(filename: &Path, value: &T) -> Result<(), RkmError> {
+    let append_line = || -> io::Result<()> {
+        if let Some(dir) = filename.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(filename)?;
+        let line = serde_json::to_string(value)?;
+        writeln!(file, "{}", line)
+    };
+    append_line().map_err(RkmError::file(filename))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::{symlink, PermissionsExt};
+    use std::path::PathBuf;
+
+    // an empty directory of its own in the temp directory
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rkm-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn lines(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn writing_through_a_symlink_keeps_the_link() {
+        let dir = scratch_dir("symlink");
+        let target = dir.join("real.csv");
+        let link = dir.join("tasks.csv");
+        fs::write(&target, "school, old task\n").unwrap();
+        symlink(&target, &link).unwrap();
+
+        write_lines_to_file(&link, &lines(&["school, new task"])).unwrap();
+        let is_link = fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink();
+        let written = fs::read_to_string(&target).unwrap();
+        let leftovers = fs::read_dir(&dir).unwrap().count();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(is_link);
+        assert_eq!(written, "school, new task\n");
+        // no temporary file is left behind
+        assert_eq!(leftovers, 2);
+    }
+
+    #[test]
+    fn writing_keeps_the_file_mode() {
+        let dir = scratch_dir("mode");
+        let path = dir.join("tasks.csv");
+        fs::write(&path, "school, old task\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+        write_lines_to_file(&path, &lines(&["school, new task", "job, other task"])).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(mode & 0o777, 0o640);
+        assert_eq!(written, "school, new task\njob, other task\n");
+    }
 }
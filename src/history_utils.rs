@@ -0,0 +1,291 @@
+use super::assignment_utils::get_tag_counter_hashmap;
+use super::errors::RkmError;
+use super::fileops_utils::append_json_line;
+use chrono::prelude::*;
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, prelude::*, BufReader};
+use std::path::{Path, PathBuf};
+
+// THESE ARE ALL FUNCTIONS RELATED TO THE SESSION
+// HISTORY LOG
+//
+
+// How a pomodoro ended
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    // the timer ran out
+    Finished,
+    // the task was marked as complete with 'c'
+    Completed,
+    // the task was rerolled with 'r'
+    Rerolled,
+    // the timer was fast forwarded with 'f'
+    Skipped,
+    // rkm was quit mid pomodoro
+    Interrupted,
+}
+
+impl Outcome {
+    // does this outcome count towards the tag counter?
+    pub fn counts_as_done(self) -> bool {
+        matches!(
+            self,
+            Outcome::Finished | Outcome::Completed | Outcome::Skipped
+        )
+    }
+}
+
+// One line of the history file
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PomodoroRecord {
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    pub tag: String,
+    pub task: String,
+    pub outcome: Outcome,
+    pub paused_secs: i64,
+    // picked with Enter rather than rolled. missing in
+    // history written before manual picks existed
+    #[serde(default)]
+    pub manual: bool,
+}
+
+impl PomodoroRecord {
+    // time actually spent working, i.e. without pauses
+    pub fn worked(&self) -> Duration {
+        self.end.signed_duration_since(self.start) - Duration::seconds(self.paused_secs)
+    }
+}
+
+// The pomodoro currently being worked on. It becomes a
+// PomodoroRecord once it ends.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ActivePomodoro {
+    start: DateTime<Local>,
+    tag: String,
+    task: String,
+    #[serde(with = "seconds")]
+    paused: Duration,
+    pause_start: Option<DateTime<Local>>,
+    skipped: bool,
+    #[serde(default)]
+    manual: bool,
+}
+
+impl ActivePomodoro {
+    pub fn new(tag: &str, task: &str) -> Self {
+        Self {
+            start: Local::now(),
+            tag: tag.to_string(),
+            task: task.to_string(),
+            paused: Duration::zero(),
+            pause_start: None,
+            skipped: false,
+            manual: false,
+        }
+    }
+
+    // a pomodoro on a task picked by hand from the task table
+    pub fn manual(tag: &str, task: &str) -> Self {
+        Self {
+            manual: true,
+            ..Self::new(tag, task)
+        }
+    }
+
+    // a pomodoro that has already been worked on for the given time,
+    // e.g. one picked up again from a saved session
+    pub fn started_ago(tag: &str, task: &str, elapsed: Duration) -> Self {
+        Self {
+            start: Local::now() - elapsed,
+            ..Self::new(tag, task)
+        }
+    }
+
+    pub fn pause(&mut self) {
+        if self.pause_start.is_none() {
+            self.pause_start = Some(Local::now());
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if let Some(pause_start) = self.pause_start.take() {
+            self.paused = self.paused + Local::now().signed_duration_since(pause_start);
+        }
+    }
+
+    // count time rkm was not running (e.g. between a crash and
+    // resuming the session) as paused
+    pub fn add_pause(&mut self, gap: Duration) {
+        self.paused = self.paused + gap;
+    }
+
+    // the task being worked on was edited from the UI
+    pub fn rename(&mut self, tag: &str, task: &str) {
+        self.tag = tag.to_string();
+        self.task = task.to_string();
+    }
+
+    // remember the timer was fast forwarded so the pomodoro
+    // is not logged as a regular finish
+    pub fn skip(&mut self) {
+        self.skipped = true;
+    }
+
+    // close off the pomodoro. a natural finish of a skipped
+    // pomodoro is logged as skipped.
+    pub fn finish(self, outcome: Outcome) -> PomodoroRecord {
+        self.finish_at(outcome, Local::now())
+    }
+
+    // close off the pomodoro as of the given time
+    pub fn finish_at(mut self, outcome: Outcome, end: DateTime<Local>) -> PomodoroRecord {
+        if let Some(pause_start) = self.pause_start.take() {
+            self.paused = self.paused + end.signed_duration_since(pause_start);
+        }
+        let outcome = match outcome {
+            Outcome::Finished if self.skipped => Outcome::Skipped,
+            other => other,
+        };
+        PomodoroRecord {
+            start: self.start,
+            end,
+            tag: self.tag,
+            task: self.task,
+            outcome,
+            paused_secs: self.paused.num_seconds(),
+            manual: self.manual,
+        }
+    }
+}
+
+// chrono durations are stored as whole seconds
+mod seconds {
+    use chrono::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(duration.num_seconds())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        Ok(Duration::seconds(i64::deserialize(deserializer)?))
+    }
+}
+
+// Append only JSONL store of every pomodoro
+pub struct HistoryLog {
+    path: PathBuf,
+}
+
+impl HistoryLog {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+        }
+    }
+
+    pub fn append(&self, record: &PomodoroRecord) -> Result<(), RkmError> {
+        append_json_line(&self.path, record)
+    }
+
+    // Read every record in the history file. A missing file is
+    // an empty history, and lines that can not be parsed (e.g.
+    // a half written line after a crash) are skipped.
+    pub fn load(&self) -> Result<Vec<PomodoroRecord>, RkmError> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(RkmError::file(&self.path)(e)),
+        };
+        let mut records = vec![];
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(RkmError::file(&self.path))?;
+            if let Ok(record) = serde_json::from_str(&line) {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+}
+
+// The pomodoros done on one task, split at every pomodoro it was
+// completed in with 'c'. A repeating task that comes back after
+// being completed starts counting again
+#[derive(Default)]
+pub struct TaskActuals {
+    // when each completed occurrence was done and its pomodoros
+    pub done: Vec<(DateTime<Local>, i64)>,
+    // pomodoros of the occurrence still open
+    pub open: i64,
+}
+
+impl TaskActuals {
+    // the pomodoros of the occurrence stamped as done at the given
+    // time. the stamp is taken right before the pomodoro is logged
+    pub fn done_at(&self, stamped: DateTime<Local>) -> i64 {
+        self.done
+            .iter()
+            .find(|(completed, _)| *completed >= stamped)
+            .map_or(0, |(_, count)| *count)
+    }
+}
+
+// Add up the pomodoros done on each task over every session,
+// keyed by tag and task name
+pub fn task_actuals(records: &[PomodoroRecord]) -> HashMap<(String, String), TaskActuals> {
+    let mut actuals: HashMap<_, TaskActuals> = HashMap::new();
+    for record in records
+        .iter()
+        .filter(|record| record.outcome.counts_as_done())
+    {
+        let task = actuals
+            .entry((record.tag.clone(), record.task.clone()))
+            .or_default();
+        task.open += 1;
+        if record.outcome == Outcome::Completed {
+            task.done.push((record.end, task.open));
+            task.open = 0;
+        }
+    }
+    actuals
+}
+
+// Count the pomodoros done on each task since it was last
+// completed, keyed by tag and task name
+pub fn task_pomodoro_counts(records: &[PomodoroRecord]) -> HashMap<(String, String), i64> {
+    task_actuals(records)
+        .into_iter()
+        .map(|(task, actuals)| (task, actuals.open))
+        .collect()
+}
+
+// Count the pomodoros done today for each tag in the config
+pub fn todays_tag_counts(
+    records: &[PomodoroRecord],
+    tag_vector: &[String],
+) -> HashMap<String, i64> {
+    tag_counts_on(records, tag_vector, Local::today())
+}
+
+// Count the pomodoros started on the given day for each tag
+pub fn tag_counts_on(
+    records: &[PomodoroRecord],
+    tag_vector: &[String],
+    day: Date<Local>,
+) -> HashMap<String, i64> {
+    let mut counts = get_tag_counter_hashmap(tag_vector);
+    for record in records
+        .iter()
+        .filter(|record| record.start.date() == day && record.outcome.counts_as_done())
+    {
+        if let Some(ctr) = counts.get_mut(&record.tag) {
+            *ctr += 1;
+        }
+    }
+    counts
+}
@@ -1,91 +1,344 @@
 mod assignment_utils;
+mod calendar_utils;
+mod date_utils;
 mod default_files;
+mod errors;
 mod fileops_utils;
+mod history_utils;
 mod posttask_utils;
+mod quota_utils;
 mod rand_utils;
+mod recency_utils;
+mod recurrence_utils;
+mod report_utils;
+mod schedule_utils;
+mod selection_utils;
+mod session_utils;
 mod settings_util;
+mod simulate_utils;
+mod stats_utils;
+mod timer_utils;
 mod ui;
+mod urgency_utils;
+mod validate_utils;
 
 use assignment_utils::{
-    convert_hashmap_to_tuplevector, create_weighttable, get_tag_counter_hashmap,
-    hashmap_to_taskvector, readin_tasks, taskvector_to_stringvect, turn_assignmentvector_into_pdf,
-    update_tagweights,
+    append_assignment, assignment_from_input, convert_hashmap_to_tuplevector, create_weighttable,
+    delete_assignment, get_tag_counter_hashmap, hashmap_to_taskvector, is_extra_field,
+    mark_assignment_complete, replace_assignment, taskvector_to_stringvect,
+    turn_assignmentvector_into_pdf, Assignment,
 };
+use chrono::prelude::*;
 use clap::ArgMatches;
-use rand_utils::roll_die;
+use errors::RkmError;
+use fileops_utils::file_fingerprint;
+use history_utils::{
+    tag_counts_on, task_pomodoro_counts, todays_tag_counts, ActivePomodoro, HistoryLog, Outcome,
+    PomodoroRecord,
+};
+use quota_utils::QuotaMode;
+use rand_utils::Dice;
 use rodio::Sink;
+use selection_utils::{
+    build_task_pool, next_strategy, strategy_from_name, Picker, RollLog, SelectionStrategy,
+    TaskPool,
+};
+use session_utils::{
+    load_snapshot, remove_snapshot, save_snapshot, Phase, Session, SessionSnapshot, TaskLock,
+    Transition,
+};
 use settings_util::ConfigOptions;
-use std::error::Error;
+use simulate_utils::SimulationOptions;
+use stats_utils::compute_history_stats;
+use std::collections::HashMap;
 use std::fs;
-use std::io;
-use std::path::Path;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use termion::event::Key;
 use termion::input::MouseTerminal;
 use termion::raw::IntoRawMode;
 use termion::screen::AlternateScreen;
+use timer_utils::Timer;
 //use tui::backend::TermionBackend;
 use tui::backend::CrosstermBackend;
 use tui::layout::{Constraint, Direction, Layout};
 use tui::Terminal;
 use ui::event::{Event, Events};
 use ui::{
-    draw_current_task, draw_gauge, draw_help, draw_tag_counter, draw_task_table, draw_weights, App,
-    HelpTable, WeightTable,
+    draw_current_task, draw_delete_confirm, draw_gauge, draw_help, draw_history, draw_tag_counter,
+    draw_task_form, draw_task_table, draw_urgency, draw_weights, draw_why, App, FormAction,
+    HelpTable, TaskForm, WeightTable,
 };
 
 #[macro_use]
 extern crate pathsep;
 
+// how often the running session is saved to the state file
+const SNAPSHOT_INTERVAL_SECS: i64 = 30;
+
+// what a task file the UI could not read in is shown as
+const TASKS_NOT_RELOADED: &str = "task file not reloaded";
+
+// Break down how likely the given task is to come up: the tag's
+// weight at each step of build_task_pool, its due date probability
+// within the tag and the two combined. Shown in the 'y' popup.
+fn explain_pick(
+    config: &ConfigOptions,
+    pool: &TaskPool,
+    assign: &Assignment,
+    picker: &Picker,
+) -> Result<Vec<(String, String)>, RkmError> {
+    let percent = |prob: f64| format!("{:.2}%", prob * 100.0);
+    let last_pick = picker.last_pick_of(assign);
+    let picked_by = last_pick.map_or(picker.strategy_name(), |record| record.strategy.as_str());
+    let mut rows = vec![
+        (String::from("Task"), assign.name.clone()),
+        (String::from("Tag"), assign.tag.clone()),
+        (String::from("Picked by"), String::from(picked_by)),
+    ];
+    if picked_by != "weighted" {
+        rows.push((
+            String::new(),
+            String::from("(the odds below are what the weighted strategy would use)"),
+        ));
+    }
+
+    let tag_idx = match config.tags.iter().position(|tag| *tag == assign.tag) {
+        Some(tag_idx) => tag_idx,
+        None => {
+            rows.push((
+                String::new(),
+                String::from("tag is no longer in the config"),
+            ));
+            return Ok(rows);
+        }
+    };
+    rows.push((
+        format!("Tag weight ({})", pool.weights_source),
+        percent(pool.configured_tag_weights[tag_idx]),
+    ));
+    rows.push((
+        String::from("After update_tagweights"),
+        format!(
+            "{} (weight of empty or zero tags shared out)",
+            percent(pool.redistributed_tag_weights[tag_idx])
+        ),
+    ));
+    if config.quota_mode != QuotaMode::Off {
+        rows.push((
+            String::from("After quota balancing"),
+            percent(pool.balanced_tag_weights[tag_idx]),
+        ));
+    }
+    if config.recency_boost > 1.0 {
+        rows.push((
+            String::from("After recency boost"),
+            percent(pool.tag_weights[tag_idx]),
+        ));
+    }
+
+    let assign_vec = &pool.candidates[&assign.tag];
+    let task_idx = match assign_vec.iter().position(|candidate| candidate == assign) {
+        Some(task_idx) => task_idx,
+        None => {
+            rows.push((
+                String::new(),
+                String::from("task can not be picked right now (done, edited or recently served)"),
+            ));
+            return Ok(rows);
+        }
+    };
+    let assign_pdf = turn_assignmentvector_into_pdf(
+        assign_vec,
+        config.use_due_dates[tag_idx],
+        &config.urgency[tag_idx],
+        &pool.recency,
+        pool.now,
+        pool.priority_boosts[tag_idx],
+        config.undated_weight,
+    )?;
+    let due_model = if config.use_due_dates[tag_idx] {
+        config.urgency[tag_idx].describe()
+    } else {
+        String::from("uniform (use_due_dates off)")
+    };
+    let priority_boost = pool.priority_boosts[tag_idx];
+    if priority_boost > 1.0 {
+        rows.push((
+            String::from("Priority"),
+            match &assign.priority {
+                Some(priority) => format!(
+                    "{} ({}x as likely as no priority)",
+                    priority,
+                    priority_boost.powi(assign.priority_level())
+                ),
+                None => String::from("none"),
+            },
+        ));
+    }
+    rows.push((
+        String::from("Probability within tag"),
+        format!(
+            "{} of {} tasks in the tag, {}",
+            percent(assign_pdf[task_idx]),
+            assign_vec.len(),
+            due_model
+        ),
+    ));
+    rows.push((
+        String::from("Total probability"),
+        percent(pool.tag_weights[tag_idx] * assign_pdf[task_idx]),
+    ));
+
+    // what the dice actually did, if this was the last pick
+    if let Some(record) = last_pick {
+        for roll in &record.rolls {
+            rows.push((
+                format!("Rolled for {}", roll.label),
+                format!(
+                    "drew {:.4} -> {}",
+                    roll.draw,
+                    roll.options
+                        .get(roll.index)
+                        .map_or("?", |option| option.as_str())
+                ),
+            ));
+        }
+    }
+    Ok(rows)
+}
+
+// the table of weights for the stats page
+fn pool_weighttable(config: &ConfigOptions, pool: &TaskPool) -> Result<WeightTable, RkmError> {
+    let rows = create_weighttable(&pool.candidates, &pool.context(config, &[]))?;
+    Ok(WeightTable::new(rows, pool.weights_source.clone()))
+}
+
 // this function reads in the task list provided in
-// settings and then randomly selects one task to
-// perform. the function returns two string vectors.
-// one corresponds to the specific task that was
-// chosen, and the other corresponds to the updated
-// table of tasks to display. these values are fed
-// into the UI.
+// settings and then lets the picker's selection strategy
+// pick one task to perform. the function returns the assignment that
+// was chosen along with the list of tasks to display
+// and the string table of weights for the stats page.
+// these values are fed into the UI.
 fn choose_task(
-    // read in tasks
-    //  from the task file, the vector of tags,
-    //  the vector of tag weights, and the
-    //  vector of booleans denoting whether or not to
-    //  use tag weights
-    configured_task_path: &Path,
-    vector_of_tags: &[String],
-    initial_tag_weights: &[f64],
-    configured_use_of_due_dates: &[bool],
-) -> (Vec<String>, Vec<Vec<String>>, Vec<Vec<String>>) {
-    let tag_to_vector_map = readin_tasks(configured_task_path, vector_of_tags);
-
-    let configured_relative_tag_weights =
-        update_tagweights(&tag_to_vector_map, initial_tag_weights, vector_of_tags);
-
-    let weighttable_vec = create_weighttable(
-        &tag_to_vector_map,
-        vector_of_tags,
-        &configured_relative_tag_weights,
-        configured_use_of_due_dates,
-    );
+    config: &ConfigOptions,
+    picker: &mut Picker,
+    history: &[PomodoroRecord],
+    tag_ctr: &HashMap<String, i64>,
+) -> Result<(Assignment, Vec<Assignment>, WeightTable), RkmError> {
+    let pool = build_task_pool(config, history, tag_ctr, Local::now())?;
+    let weight_table = pool_weighttable(config, &pool)?;
 
     // last Vec will become the stats page
-    // roll a assignment
-    // first pick a tag to get an assignment from
-    let tag_roll = roll_die(&configured_relative_tag_weights);
-    let chosen_tag = &vector_of_tags[tag_roll];
-
-    // then get the vector of assignments assigned to that tag
-    let assignvector = tag_to_vector_map.get(chosen_tag).unwrap();
-    // turn this into a pdf and roll an assignment
-    let assignvector_pdf =
-        turn_assignmentvector_into_pdf(assignvector, configured_use_of_due_dates[tag_roll]);
-    let chosen_assign = &assignvector[roll_die(&assignvector_pdf)];
-
-    // generate table string and current task string. this is for the tui
-    let assign_string = taskvector_to_stringvect(chosen_assign);
-    let string_alltask_vec = hashmap_to_taskvector(&tag_to_vector_map, vector_of_tags);
-    (assign_string, string_alltask_vec, weighttable_vec)
+    // pick an assignment
+    let chosen_assign = picker
+        .pick(&pool.candidates, &pool.context(config, history))?
+        .ok_or_else(|| RkmError::EmptyTaskList(config.task_path.clone()))?;
+
+    // list every task. this is for the tui
+    let alltask_vec = hashmap_to_taskvector(&pool.all_tasks, &config.tags);
+    Ok((chosen_assign, alltask_vec, weight_table))
 }
 
-fn load_or_create_configuration_file(args: &ArgMatches) -> io::Result<String> {
+// read in the task list and build the task list and weight
+// table shown in the UI without rolling a new task
+fn list_tasks(
+    config: &ConfigOptions,
+    history: &[PomodoroRecord],
+    tag_ctr: &HashMap<String, i64>,
+) -> Result<(Vec<Assignment>, WeightTable), RkmError> {
+    let pool = build_task_pool(config, history, tag_ctr, Local::now())?;
+    let weight_table = pool_weighttable(config, &pool)?;
+    let alltask_vec = hashmap_to_taskvector(&pool.all_tasks, &config.tags);
+    Ok((alltask_vec, weight_table))
+}
+
+// what the COUNTER box shows. tags with a daily quota
+// show how far along they are
+fn tag_counter(config: &ConfigOptions, tag_ctr: &HashMap<String, i64>) -> Vec<(String, String)> {
+    convert_hashmap_to_tuplevector(tag_ctr, &config.tags)
+        .into_iter()
+        .enumerate()
+        .map(
+            |(tag_idx, (tag, count))| match config.quota_mode.quota(tag_idx) {
+                Some(quota) => (tag, format!("{}/{}", count, quota)),
+                None => (tag, count),
+            },
+        )
+        .collect()
+}
+
+// how long a phase lasts in minutes
+fn phase_minutes(config: &ConfigOptions, phase: &Phase) -> i64 {
+    match phase {
+        Phase::Work => config.task_time,
+        Phase::ShortBreak => config.min_break_time,
+        Phase::LongBreak => config.max_break_time,
+        Phase::Paused(phase) => phase_minutes(config, phase),
+    }
+}
+
+// what the CURRENT TASK box shows during a phase. done is
+// the number of pomodoros already spent on the task
+fn phase_banner(phase: &Phase, assign: &Assignment, done: i64) -> Vec<String> {
+    match phase {
+        Phase::Work => taskvector_to_stringvect(assign, done),
+        Phase::ShortBreak => vec![String::from("TAKE A CHILL PILL\n")],
+        Phase::LongBreak => vec![String::from("TAKE A LOONG CHILL PILL\n")],
+        Phase::Paused(phase) => {
+            let mut banner = phase_banner(phase, assign, done);
+            banner.push("PAUSED".to_string());
+            banner
+        }
+    }
+}
+
+// ask on the command line whether to pick up an interrupted session
+fn ask_to_resume(snapshot: &SessionSnapshot) -> io::Result<bool> {
+    print!(
+        "Found an interrupted session from {} (working on: {}). Resume it? [Y/n] ",
+        snapshot.saved.format("%Y-%m-%d %H:%M"),
+        snapshot.task.name
+    );
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(!answer.trim().to_lowercase().starts_with('n'))
+}
+
+// the --seed given on the command line, if any
+fn seed_arg(matches: &ArgMatches) -> Result<Option<u64>, RkmError> {
+    match matches.value_of("seed") {
+        Some(seed) => Ok(Some(seed.parse::<u64>().map_err(|_| {
+            RkmError::BadValue {
+                key: "--seed",
+                reason: format!("'{}' is not a whole number", seed),
+            }
+        })?)),
+        None => Ok(None),
+    }
+}
+
+// a count above 0 given on the command line, or the default
+fn count_arg(
+    matches: &ArgMatches,
+    name: &str,
+    key: &'static str,
+    default: usize,
+) -> Result<usize, RkmError> {
+    match matches.value_of(name) {
+        Some(value) => match value.parse::<usize>() {
+            Ok(count) if count > 0 => Ok(count),
+            _ => Err(RkmError::BadValue {
+                key,
+                reason: format!("'{}' is not a whole number above 0", value),
+            }),
+        },
+        None => Ok(default),
+    }
+}
+
+fn load_or_create_configuration_file(args: &ArgMatches) -> Result<String, RkmError> {
     if let Some(c) = args.value_of("config") {
         println!("Value for config: {}", c);
         Ok(c.to_string())
@@ -94,18 +347,447 @@ fn load_or_create_configuration_file(args: &ArgMatches) -> io::Result<String> {
             config_dir.push("rusty-krab-manager");
             if !config_dir.exists() {
                 println!("Generating config directories...");
-                fs::create_dir_all(config_dir)?;
+                fs::create_dir_all(&config_dir).map_err(RkmError::file(&config_dir))?;
             }
         }
-        default_files::create();
-        let mut fullpath = dirs::config_dir().unwrap();
-        fullpath.push("rusty-krab-manager");
-        fullpath.push("config.toml");
-        Ok(fullpath.to_str().unwrap().to_string())
+        default_files::create()?;
+        let fullpath = settings_util::default_data_path("config.toml");
+        Ok(fullpath.to_string_lossy().to_string())
     }
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
+// Everything the main loop changes as the session moves
+// from phase to phase
+struct Manager {
+    config: ConfigOptions,
+    app: App,
+    weight_table: WeightTable,
+    history: HistoryLog,
+    session: Session,
+    // None when muted
+    sink: Option<Sink>,
+    picker: Picker,
+    tag_ctr: HashMap<String, i64>,
+    // the day tag_ctr counts the pomodoros of
+    counted_on: Date<Local>,
+    curr_assign: Assignment,
+    curr_pomodoro: Option<ActivePomodoro>,
+    // task picked by hand for the coming pomodoros
+    locked: Option<TaskLock>,
+    last_snapshot: DateTime<Local>,
+    // what the watched files held when rkm last read or wrote them
+    seen: HashMap<PathBuf, Option<u64>>,
+}
+
+impl Manager {
+    // save everything needed to resume the session to the state file
+    fn snapshot(&mut self) -> Result<(), RkmError> {
+        let snapshot = SessionSnapshot {
+            saved: Local::now(),
+            phase: self.session.phase().clone(),
+            elapsed_secs: self.app.timer.elapsed().num_seconds(),
+            short_breaks_taken: self.session.short_breaks_taken(),
+            task: self.curr_assign.clone(),
+            pomodoro: self.curr_pomodoro.clone(),
+            tag_ctr: self.tag_ctr.clone(),
+            locked: self.locked.clone(),
+        };
+        save_snapshot(&self.config.state_path, &snapshot)?;
+        self.last_snapshot = snapshot.saved;
+        Ok(())
+    }
+
+    // start counting afresh once the day is over, e.g. for
+    // a session running past midnight
+    fn roll_over_day(&mut self) -> Result<(), RkmError> {
+        let today = Local::today();
+        if today != self.counted_on {
+            self.tag_ctr = tag_counts_on(&self.history.load()?, &self.config.tags, today);
+            self.app.completed = tag_counter(&self.config, &self.tag_ctr);
+            self.counted_on = today;
+        }
+        Ok(())
+    }
+
+    // roll a new task and show it
+    fn reroll(&mut self) -> Result<(), RkmError> {
+        self.roll_over_day()?;
+        let (new_assign, items_to_list, weight_table) = choose_task(
+            &self.config,
+            &mut self.picker,
+            &self.history.load()?,
+            &self.tag_ctr,
+        )?;
+        posttask_utils::nextupnotif(&new_assign.name).ok();
+        self.weight_table = weight_table;
+        self.app.current_task =
+            taskvector_to_stringvect(&new_assign, self.app.done_on(&new_assign));
+        self.app.set_items(items_to_list);
+        self.curr_pomodoro = Some(ActivePomodoro::new(&new_assign.tag, &new_assign.name));
+        self.curr_assign = new_assign;
+        Ok(())
+    }
+
+    // work on the given task without rolling for it
+    fn start_manual(&mut self, assign: Assignment) -> Result<(), RkmError> {
+        self.picker.pick_by_hand(&assign)?;
+        posttask_utils::nextupnotif(&assign.name).ok();
+        self.app.current_task = taskvector_to_stringvect(&assign, self.app.done_on(&assign));
+        self.curr_pomodoro = Some(ActivePomodoro::manual(&assign.tag, &assign.name));
+        self.curr_assign = assign;
+        Ok(())
+    }
+
+    // The task picked with Enter. While working on another task
+    // it is switched to right away, otherwise it is locked in for
+    // the next pomodoro. Every further pick of the same task
+    // locks it in for one more pomodoro.
+    fn pick_by_hand(&mut self, assign: Assignment) -> Result<(), RkmError> {
+        let switch_now = self.session.is_working() && assign != self.curr_assign;
+        if switch_now {
+            self.end_pomodoro(Outcome::Rerolled)?;
+            self.start_manual(assign)?;
+            self.locked = None;
+        } else {
+            let pomodoros = match &self.locked {
+                Some(lock) if lock.task == assign => lock.pomodoros + 1,
+                _ => 1,
+            };
+            self.locked = Some(TaskLock {
+                task: assign,
+                pomodoros,
+            });
+        }
+
+        let more = self.locked.as_ref().map_or(0, |lock| lock.pomodoros);
+        self.app.status = Some(format!(
+            "picked by hand, locked for {} more pomodoro(s)",
+            more
+        ));
+        self.snapshot()
+    }
+
+    // the next pomodoro starts. a task locked in by hand
+    // is worked on, anything else is rolled
+    fn next_task(&mut self) -> Result<(), RkmError> {
+        match self.locked.take() {
+            Some(lock) => {
+                if lock.pomodoros > 1 {
+                    self.locked = Some(TaskLock {
+                        task: lock.task.clone(),
+                        pomodoros: lock.pomodoros - 1,
+                    });
+                }
+                self.start_manual(lock.task)
+            }
+            None => self.reroll(),
+        }
+    }
+
+    // read the task file again after it was changed from the UI.
+    // a task file rkm can not run off is shown rather than fatal
+    fn refresh_tasks(&mut self) {
+        self.seen.insert(
+            self.config.task_path.clone(),
+            file_fingerprint(&self.config.task_path),
+        );
+        let listed = self
+            .history
+            .load()
+            .and_then(|history| list_tasks(&self.config, &history, &self.tag_ctr));
+        match listed {
+            Ok((items_to_list, weight_table)) => {
+                self.app.set_items(items_to_list);
+                self.weight_table = weight_table;
+                // anything else shown is still news
+                if self
+                    .app
+                    .status
+                    .as_deref()
+                    .map_or(false, |status| status.starts_with(TASKS_NOT_RELOADED))
+                {
+                    self.app.status = None;
+                }
+            }
+            Err(e) => self.app.status = Some(format!("{}: {}", TASKS_NOT_RELOADED, e)),
+        }
+    }
+
+    // read the config again after it was changed on disk. a config
+    // that does not validate is shown and the old one is kept.
+    // phase lengths take effect from the next phase on
+    fn reload_config(&mut self, config_path: &str) {
+        let config = match settings_util::readin_settings(config_path) {
+            Ok(config) => config,
+            Err(e) => {
+                self.app.status = Some(format!("config not reloaded: {}", e));
+                return;
+            }
+        };
+
+        // carry over today's counts for tags that are still around
+        let mut tag_ctr = get_tag_counter_hashmap(&config.tags);
+        for (tag, count) in &self.tag_ctr {
+            if let Some(ctr) = tag_ctr.get_mut(tag) {
+                *ctr = *count;
+            }
+        }
+        self.tag_ctr = tag_ctr;
+
+        if let Some(sink) = &self.sink {
+            sink.set_volume(config.sound_volume as f32);
+        }
+        // only switch if the config changed, not what was picked with 'm'
+        if config.selection_strategy != self.config.selection_strategy {
+            if let Ok(strategy) = strategy_from_name(&config.selection_strategy) {
+                self.set_strategy(strategy);
+            }
+        }
+        self.session.set_maxno_short_breaks(config.maxno_min_breaks);
+        self.history = HistoryLog::new(&config.history_path);
+        self.picker
+            .set_log(Some(RollLog::new(&config.roll_log_path)));
+        self.config = config;
+        self.app.completed = tag_counter(&self.config, &self.tag_ctr);
+        self.refresh_tasks();
+    }
+
+    // the numbers behind the current task, as of now
+    fn explain_current(&self) -> Result<Vec<(String, String)>, RkmError> {
+        let pool = build_task_pool(
+            &self.config,
+            &self.history.load()?,
+            &self.tag_ctr,
+            Local::now(),
+        )?;
+        explain_pick(&self.config, &pool, &self.curr_assign, &self.picker)
+    }
+
+    // pick the next tasks a different way from now on
+    fn set_strategy(&mut self, strategy: Box<dyn SelectionStrategy>) {
+        self.app.strategy = strategy.name();
+        self.picker.set_strategy(strategy);
+    }
+
+    // the files rkm reloads when they change on disk. a new
+    // holiday calendar is read in with the config
+    fn watched_files(&self, config_path: &str) -> Vec<PathBuf> {
+        let mut files = vec![PathBuf::from(config_path), self.config.task_path.clone()];
+        files.extend(self.config.holidays_path.clone());
+        files
+    }
+
+    // watch the files rkm reloads, as of what they hold now
+    fn watch_files(&mut self, events: &mut Events, config_path: &str) {
+        let files = self.watched_files(config_path);
+        for path in &files {
+            self.seen.insert(path.clone(), file_fingerprint(path));
+        }
+        if let Err(e) = events.watch(files) {
+            self.app.status = Some(format!("not watching for changes: {}", e));
+        }
+    }
+
+    // whether a watched file holds something else than when rkm last
+    // read or wrote it. rkm's own writes and the further events of a
+    // change already seen are not
+    fn is_news(&mut self, path: &Path) -> bool {
+        let fingerprint = file_fingerprint(path);
+        fingerprint.is_some()
+            && self.seen.insert(path.to_path_buf(), fingerprint) != Some(fingerprint)
+    }
+
+    // write a task added or edited in the UI to the task file
+    fn save_task(
+        &mut self,
+        old_assign: Option<&Assignment>,
+        new_assign: Assignment,
+    ) -> Result<(), RkmError> {
+        let saved = match old_assign {
+            Some(old_assign) => replace_assignment(&self.config.task_path, old_assign, &new_assign),
+            None => append_assignment(&self.config.task_path, &new_assign).map(|_| true),
+        };
+        match saved {
+            Ok(true) => {}
+            Ok(false) => {
+                self.app.status = Some(String::from("task is no longer in the task file"));
+                return Ok(());
+            }
+            Err(e) => {
+                self.app.status = Some(e.to_string());
+                return Ok(());
+            }
+        }
+
+        // keep working on the current task if that is what was edited
+        if old_assign == Some(&self.curr_assign) {
+            if let Some(pomodoro) = self.curr_pomodoro.as_mut() {
+                pomodoro.rename(&new_assign.tag, &new_assign.name);
+            }
+            self.curr_assign = new_assign.clone();
+            self.app.current_task = phase_banner(
+                self.session.phase(),
+                &self.curr_assign,
+                self.app.done_on(&self.curr_assign),
+            );
+        }
+        if let Some(lock) = self.locked.as_mut() {
+            if old_assign == Some(&lock.task) {
+                lock.task = new_assign;
+            }
+        }
+        self.refresh_tasks();
+        self.snapshot()
+    }
+
+    // mark the current task as done in the task file and roll a new
+    // one. a recurring task comes back with its next due date. with
+    // no task left to roll the done one stays up until one is added
+    fn complete_task(&mut self) -> Result<(), RkmError> {
+        match mark_assignment_complete(&self.config.task_path, &self.curr_assign) {
+            Ok(true) => {}
+            Ok(false) => {
+                self.app.status = Some(String::from("task is no longer in the task file"));
+                return Ok(());
+            }
+            Err(e) => {
+                self.app.status = Some(e.to_string());
+                return Ok(());
+            }
+        }
+
+        let next = self.curr_assign.next_occurrence(Local::now());
+        self.locked = None;
+        self.end_pomodoro(Outcome::Completed)?;
+        self.count_done();
+        let rolled = self.reroll();
+        self.refresh_tasks();
+        self.app.status = match (rolled, next) {
+            (Err(e), _) | (_, Err(e)) => Some(e.to_string()),
+            (Ok(()), Ok(next)) => {
+                next.map(|next| format!("'{}' is back, due {}", next.name, next.due_label()))
+            }
+        };
+        self.snapshot()
+    }
+
+    // remove a task from the task file. if it was being worked
+    // on a new one is rolled
+    fn delete_task(&mut self, old_assign: &Assignment) -> Result<(), RkmError> {
+        match delete_assignment(&self.config.task_path, old_assign) {
+            Ok(true) => {}
+            Ok(false) => {
+                self.app.status = Some(String::from("task is no longer in the task file"));
+                return Ok(());
+            }
+            Err(e) => {
+                self.app.status = Some(e.to_string());
+                return Ok(());
+            }
+        }
+
+        if self
+            .locked
+            .as_ref()
+            .map_or(false, |lock| lock.task == *old_assign)
+        {
+            self.locked = None;
+        }
+        if *old_assign == self.curr_assign && self.session.is_working() {
+            self.end_pomodoro(Outcome::Rerolled)?;
+            if let Err(e) = self.reroll() {
+                self.app.status = Some(e.to_string());
+            }
+        }
+        self.refresh_tasks();
+        self.snapshot()
+    }
+
+    // log the pomodoro being worked on, if there is one
+    fn end_pomodoro(&mut self, outcome: Outcome) -> Result<(), RkmError> {
+        if let Some(pomodoro) = self.curr_pomodoro.take() {
+            self.history.append(&pomodoro.finish(outcome))?;
+            self.app.actuals = task_pomodoro_counts(&self.history.load()?);
+        }
+        Ok(())
+    }
+
+    // a timer ran out. play the sound and show a notification.
+    // a missing notification daemon is no reason to stop the timer
+    fn announce_finish(&self) -> Result<(), RkmError> {
+        if let Some(sink) = &self.sink {
+            posttask_utils::playsound(&self.config.sound_path, sink)?;
+        }
+        posttask_utils::finishnotif().ok();
+        Ok(())
+    }
+
+    // add one to the tag counter of the current task
+    fn count_done(&mut self) {
+        if let Some(ctr) = self.tag_ctr.get_mut(&self.curr_assign.tag) {
+            *ctr += 1;
+        }
+        self.app.completed = tag_counter(&self.config, &self.tag_ctr);
+    }
+
+    // react to the session moving from one phase to another
+    fn apply(&mut self, transition: Transition) -> Result<(), RkmError> {
+        match (&transition.from, &transition.to) {
+            // pausing or unpausing freezes or thaws whatever is running
+            (_, Phase::Paused(_)) => {
+                self.app.timer.pause();
+                self.app.current_task.push("PAUSED".to_string());
+                if let Some(pomodoro) = self.curr_pomodoro.as_mut() {
+                    pomodoro.pause();
+                }
+            }
+            (Phase::Paused(_), _) => {
+                self.app.timer.resume();
+                self.app.current_task.pop();
+                if let Some(pomodoro) = self.curr_pomodoro.as_mut() {
+                    pomodoro.resume();
+                }
+            }
+
+            // task time is up. log it, count it and start the break
+            (Phase::Work, Phase::ShortBreak) | (Phase::Work, Phase::LongBreak) => {
+                self.announce_finish()?;
+                self.end_pomodoro(Outcome::Finished)?;
+                self.count_done();
+                self.app.current_task = phase_banner(
+                    &transition.to,
+                    &self.curr_assign,
+                    self.app.done_on(&self.curr_assign),
+                );
+                self.app.timer = Timer::start(phase_minutes(&self.config, &transition.to));
+            }
+
+            // break is over. reroll the task, or take the one
+            // picked by hand, and get back to work
+            (_, Phase::Work) => {
+                self.announce_finish()?;
+                // e.g. every task is done. shown until one is added
+                if let Err(e) = self.next_task() {
+                    self.app.status = Some(e.to_string());
+                }
+                self.app.timer = Timer::start(self.config.task_time);
+            }
+
+            _ => {}
+        }
+        self.snapshot()
+    }
+}
+
+fn main() {
+    // report errors only once the terminal is restored
+    if let Err(e) = run() {
+        eprintln!("rusty-krab-manager: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), RkmError> {
     let matches = clap::Command::new("Rusty-Krab-Manager")
         .about("Pomodoro inspired TUI task manager")
         .author("Arya K.")
@@ -116,167 +798,508 @@ fn main() -> Result<(), Box<dyn Error>> {
                 .long("config")
                 .value_name("FILE")
                 .help("Path for a config file")
-                .takes_value(true),
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            clap::Arg::new("resume")
+                .long("resume")
+                .help("Resume an interrupted session without asking")
+                .conflicts_with("fresh"),
+        )
+        .arg(
+            clap::Arg::new("fresh")
+                .long("fresh")
+                .help("Discard an interrupted session and start a new one"),
+        )
+        .arg(
+            clap::Arg::new("seed")
+                .long("seed")
+                .value_name("N")
+                .help("Seed the dice so the same rolls come up again")
+                .takes_value(true)
+                .global(true),
         )
         .arg(
             clap::Arg::new("mute")
                 .short('m')
                 .long("mute")
-                .help("Do not play sound after ")
-                .takes_value(true),
+                .help("Do not play sound after a timer runs out"),
+        )
+        .subcommand(
+            clap::Command::new("check")
+                .about("Report every problem in the config and task file without starting"),
+        )
+        .subcommand(
+            clap::Command::new("add")
+                .about("Add a task to the task file without starting")
+                .arg(clap::Arg::new("tag").required(true).help("One of the tags in the config"))
+                .arg(clap::Arg::new("name").required(true).help("What the task is"))
+                .arg(clap::Arg::new("due").help(
+                    "When it is due, e.g. 2026-11-02 17:00, 2026-11-02, tomorrow 17:00, fri, +3d \
+                     or next week. Left out for an open-ended task",
+                ))
+                .arg(
+                    clap::Arg::new("extras")
+                        .multiple_values(true)
+                        .help("Priority (! !! !!! or C B A), estimate (~3) or repeat rule (@weekly mon)"),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("report")
+                .about("Compare the pomodoro estimates of done tasks with what they took, by tag"),
+        )
+        .subcommand(
+            clap::Command::new("simulate")
+                .about("Roll many days of pomodoros without starting and print how often each task comes up")
+                .arg(
+                    clap::Arg::new("runs")
+                        .long("runs")
+                        .value_name("N")
+                        .help("How many days to simulate [default: 1000]")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::new("weekday")
+                        .long("weekday")
+                        .value_name("DAY")
+                        .help("Whose weights to use, e.g. mon [default: today]")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::new("pomodoros")
+                        .long("pomodoros")
+                        .value_name("N")
+                        .help("Pomodoros per day [default: 8]")
+                        .takes_value(true),
+                )
+                .arg(
+                    clap::Arg::new("start")
+                        .long("start")
+                        .value_name("HH:MM")
+                        .help("When the first pomodoro starts [default: 09:00]")
+                        .takes_value(true),
+                ),
         )
         .get_matches();
 
-    let config = load_or_create_configuration_file(&matches)?;
+    let config_path = load_or_create_configuration_file(&matches)?;
+
+    if matches.subcommand_matches("check").is_some() {
+        if !validate_utils::run_check(&config_path) {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(add_matches) = matches.subcommand_matches("add") {
+        let config = settings_util::parse_settings(&config_path)?;
+        let tag = add_matches.value_of("tag").unwrap_or_default();
+        if !config.tags.iter().any(|known| known == tag) {
+            return Err(RkmError::UnknownTag(tag.to_string()));
+        }
+        let mut due = add_matches.value_of("due").unwrap_or_default();
+        let mut extras: Vec<&str> = add_matches
+            .values_of("extras")
+            .map_or_else(Vec::new, |extras| extras.collect());
+        // an open-ended task can go straight on to its extras
+        if is_extra_field(due) {
+            extras.insert(0, due);
+            due = "";
+        }
+        let assign = assignment_from_input(
+            tag,
+            add_matches.value_of("name").unwrap_or_default(),
+            due,
+            &extras.join(", "),
+            Local::now(),
+        )?;
+        append_assignment(&config.task_path, &assign)?;
+        println!(
+            "added to {}: {}",
+            config.task_path.display(),
+            assign.to_line()
+        );
+        return Ok(());
+    }
+
+    if matches.subcommand_matches("report").is_some() {
+        return report_utils::run_report(&config_path);
+    }
+
+    if let Some(sim_matches) = matches.subcommand_matches("simulate") {
+        let config = settings_util::parse_settings(&config_path)?;
+        let weekday = match sim_matches.value_of("weekday") {
+            Some(day) => settings_util::WEEKDAYS
+                .iter()
+                .position(|weekday| *weekday == day.to_lowercase())
+                .ok_or_else(|| RkmError::BadValue {
+                    key: "--weekday",
+                    reason: format!(
+                        "'{}' is not one of {}",
+                        day,
+                        settings_util::WEEKDAYS.join(", ")
+                    ),
+                })?,
+            None => Local::now().weekday().num_days_from_monday() as usize,
+        };
+        let start = match sim_matches.value_of("start") {
+            Some(start) => {
+                NaiveTime::parse_from_str(start, "%H:%M").map_err(|_| RkmError::BadValue {
+                    key: "--start",
+                    reason: format!("'{}' is not a time like 09:00", start),
+                })?
+            }
+            None => NaiveTime::from_hms(9, 0, 0),
+        };
+        let options = SimulationOptions {
+            runs: count_arg(sim_matches, "runs", "--runs", 1000)?,
+            weekday,
+            pomodoros: count_arg(sim_matches, "pomodoros", "--pomodoros", 8)?,
+            start,
+            // the same seed as the TUI would roll with
+            seed: seed_arg(sim_matches)?.or(config.seed),
+        };
+        return simulate_utils::run_simulation(&config, &options);
+    }
 
     // set config variables
-    let ConfigOptions {
-        task_path,
-        sound_path,
-        sound_volume,
-        tags,
-        use_due_dates,
-        initial_tag_weights,
-        min_break_time,
-        max_break_time,
-        task_time,
-        maxno_min_breaks,
-    } = settings_util::readin_settings(&config)?;
-
-    // initialize audio sink
-    let (_stream, stream_handle) = rodio::OutputStream::try_default().unwrap();
-    let sink = Sink::try_new(&stream_handle).unwrap();
-    sink.set_volume(sound_volume as f32);
-
-    // initialize tag counter
-    let mut tag_ctr = get_tag_counter_hashmap(&tags);
-
-    // Choose initial task
-    let (curr_task, items_to_list, weighttable_vec) =
-        choose_task(&task_path, &tags, &initial_tag_weights, &use_due_dates);
-    posttask_utils::nextupnotif(&curr_task[1])?;
+    let config = settings_util::readin_settings(&config_path)?;
+
+    // initialize audio sink. the stream has to outlive the sink
+    let (_stream, sink) = if matches.is_present("mute") {
+        (None, None)
+    } else {
+        let no_audio = |e: &dyn std::fmt::Display| {
+            RkmError::Audio(format!("{} (use --mute to run without sound)", e))
+        };
+        let (stream, stream_handle) =
+            rodio::OutputStream::try_default().map_err(|e| no_audio(&e))?;
+        let sink = Sink::try_new(&stream_handle).map_err(|e| no_audio(&e))?;
+        sink.set_volume(config.sound_volume as f32);
+        (Some(stream), Some(sink))
+    };
+
+    // initialize tag counter with what was already done today
+    let history = HistoryLog::new(&config.history_path);
+    // a seed on the command line wins over one in the config
+    let seed = seed_arg(&matches)?.or(config.seed);
+    let mut picker = Picker::new(
+        strategy_from_name(&config.selection_strategy)?,
+        Dice::new(seed),
+        Some(RollLog::new(&config.roll_log_path)),
+    );
+    let mut tag_ctr = todays_tag_counts(&history.load()?, &config.tags);
+
+    // was the last session interrupted? if so, resume it or log
+    // whatever was being worked on as interrupted
+    let snapshot = match load_snapshot(&config.state_path) {
+        Some(snapshot) if matches.is_present("fresh") => Err(snapshot),
+        Some(snapshot) if matches.is_present("resume") => Ok(Some(snapshot)),
+        Some(snapshot) => {
+            if ask_to_resume(&snapshot).map_err(RkmError::Terminal)? {
+                Ok(Some(snapshot))
+            } else {
+                Err(snapshot)
+            }
+        }
+        None => Ok(None),
+    };
+    let snapshot = match snapshot {
+        Ok(snapshot) => snapshot,
+        Err(stale) => {
+            if let Some(pomodoro) = stale.pomodoro {
+                history.append(&pomodoro.finish_at(Outcome::Interrupted, stale.saved))?;
+            }
+            remove_snapshot(&config.state_path)?;
+            None
+        }
+    };
+
+    let locked = snapshot
+        .as_ref()
+        .and_then(|snapshot| snapshot.locked.clone());
+    let (session, curr_assign, curr_pomodoro, timer) = match snapshot {
+        Some(snapshot) => {
+            let elapsed = chrono::Duration::seconds(snapshot.elapsed_secs);
+            let mut timer = Timer::start_from(phase_minutes(&config, &snapshot.phase), elapsed);
+            let working = snapshot.phase == Phase::Work;
+            let session = Session::restore(
+                snapshot.phase,
+                snapshot.short_breaks_taken,
+                config.maxno_min_breaks,
+            );
+            if session.is_paused() {
+                timer.pause();
+            }
+            let curr_pomodoro = match snapshot.pomodoro {
+                Some(mut pomodoro) => {
+                    // rkm was not running in between so that time was not worked
+                    if !session.is_paused() {
+                        pomodoro.add_pause(Local::now().signed_duration_since(snapshot.saved));
+                    }
+                    Some(pomodoro)
+                }
+                // the pomodoro was logged when rkm quit. a new one picks
+                // up where the timer left off, without the time in between
+                None if working => Some(ActivePomodoro::started_ago(
+                    &snapshot.task.tag,
+                    &snapshot.task.name,
+                    elapsed,
+                )),
+                None => None,
+            };
+            if snapshot.saved.date() == Local::now().date() {
+                tag_ctr = snapshot.tag_ctr;
+            }
+            (session, snapshot.task, curr_pomodoro, timer)
+        }
+        None => {
+            // Choose initial task
+            let (curr_assign, _, _) =
+                choose_task(&config, &mut picker, &history.load()?, &tag_ctr)?;
+            posttask_utils::nextupnotif(&curr_assign.name).ok();
+            let curr_pomodoro = Some(ActivePomodoro::new(&curr_assign.tag, &curr_assign.name));
+            (
+                Session::new(config.maxno_min_breaks),
+                curr_assign,
+                curr_pomodoro,
+                Timer::start(config.task_time),
+            )
+        }
+    };
+    let (items_to_list, weight_table) = list_tasks(&config, &history.load()?, &tag_ctr)?;
 
     // Terminal initialization for UI
-    let stdout = io::stdout().into_raw_mode()?;
+    let stdout = io::stdout().into_raw_mode().map_err(RkmError::Terminal)?;
     let stdout = MouseTerminal::from(stdout);
     let stdout = AlternateScreen::from(stdout);
     let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
-    terminal.hide_cursor()?;
+    let mut terminal = Terminal::new(backend).map_err(RkmError::Terminal)?;
+    terminal.hide_cursor().map_err(RkmError::Terminal)?;
 
-    let events = Events::new();
+    let mut events = Events::new();
     let mut app = App::new();
-    app.completed = convert_hashmap_to_tuplevector(&tag_ctr, &tags);
-    app.current_task = curr_task;
-    app.items = items_to_list;
+    app.completed = tag_counter(&config, &tag_ctr);
+    app.actuals = task_pomodoro_counts(&history.load()?);
+    app.current_task = phase_banner(session.phase(), &curr_assign, app.done_on(&curr_assign));
+    app.set_items(items_to_list);
+    app.timer = timer;
+    app.strategy = picker.strategy_name();
 
     // Initialize starting parameters
-    let mut min_break_ctr = 0;
-    let mut its_task_time = true;
-    let mut its_min_break_time = false;
-    let mut its_max_break_time = false;
     let mut curr_screen = String::from("tasks");
 
     // create help table and flag
     let mut help_table = HelpTable::new();
-    let mut weight_table = WeightTable::new(weighttable_vec);
+
+    // task form or delete confirmation open over the task table
+    let mut task_form: Option<TaskForm> = None;
+    let mut pending_delete: Option<Assignment> = None;
+    // rows of the "why this task?" popup while it is open
+    let mut why_rows: Option<Vec<(String, String)>> = None;
+    let mut history_days = 7;
+    let mut history_stats = compute_history_stats(
+        &history.load()?,
+        &config.tags,
+        &config.weekday_weights,
+        history_days,
+    );
+
+    let mut rkm = Manager {
+        session,
+        config,
+        app,
+        weight_table,
+        history,
+        sink,
+        picker,
+        tag_ctr,
+        counted_on: Local::today(),
+        curr_assign,
+        curr_pomodoro,
+        locked,
+        last_snapshot: Local::now(),
+        seen: HashMap::new(),
+    };
+    rkm.snapshot()?;
+    rkm.watch_files(&mut events, &config_path);
 
     // Enter into UI drawing infinite loop
     loop {
-        terminal.draw(|f| match curr_screen.as_str() {
-            "help" => {
-                let rects = Layout::default()
-                    .constraints([Constraint::Percentage(100)].as_ref())
-                    .split(f.size());
-                draw_help(f, &mut help_table, rects[0]);
+        let app = &mut rkm.app;
+        let weight_table = &mut rkm.weight_table;
+        let config = &rkm.config;
+        terminal
+            .draw(|f| match curr_screen.as_str() {
+                "help" => {
+                    let rects = Layout::default()
+                        .constraints([Constraint::Percentage(100)].as_ref())
+                        .split(f.size());
+                    draw_help(f, &mut help_table, rects[0]);
+                }
+                "stats" => {
+                    let rects = Layout::default()
+                        .constraints(
+                            [Constraint::Percentage(75), Constraint::Percentage(25)].as_ref(),
+                        )
+                        .split(f.size());
+                    draw_weights(f, weight_table, rects[0]);
+                    draw_urgency(
+                        f,
+                        &config.tags,
+                        &config.use_due_dates,
+                        &config.urgency,
+                        rects[1],
+                    );
+                }
+                "history" => {
+                    let rects = Layout::default()
+                        .constraints([Constraint::Percentage(100)].as_ref())
+                        .split(f.size());
+                    draw_history(f, &history_stats, rects[0]);
+                }
+                _ => {
+                    let chunks = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints(
+                            [
+                                Constraint::Percentage(30),
+                                Constraint::Percentage(50),
+                                Constraint::Percentage(20),
+                            ]
+                            .as_ref(),
+                        )
+                        .split(f.size());
+                    let mini_chunks = Layout::default()
+                        .direction(Direction::Horizontal)
+                        .constraints(
+                            [Constraint::Percentage(75), Constraint::Percentage(25)].as_ref(),
+                        )
+                        .split(chunks[0]);
+                    // draw_gauge(&mut f, &app, chunks[2]);
+                    draw_gauge(f, app, chunks[2]);
+                    draw_task_table(f, app, chunks[1]);
+                    draw_current_task(f, app, mini_chunks[0]);
+                    draw_tag_counter(f, app, mini_chunks[1]);
+                    if let Some(form) = &task_form {
+                        draw_task_form(f, form, f.size());
+                    } else if let Some(assign) = &pending_delete {
+                        draw_delete_confirm(f, assign, f.size());
+                    } else if let Some(rows) = &why_rows {
+                        draw_why(f, rows, f.size());
+                    }
+                }
+            })
+            .map_err(RkmError::Terminal)?;
+
+        // keybindings
+        match events.next()? {
+            // the task form takes every key while it is open
+            Event::Input(input) if task_form.is_some() => {
+                if let Some(form) = task_form.as_mut() {
+                    match form.handle_key(input) {
+                        FormAction::Continue => {}
+                        FormAction::Cancel => task_form = None,
+                        FormAction::Submit(new_assign) => {
+                            let old_assign = form.editing.clone();
+                            task_form = None;
+                            rkm.save_task(old_assign.as_ref(), new_assign)?;
+                        }
+                    }
+                }
             }
-            "stats" => {
-                let rects = Layout::default()
-                    .constraints([Constraint::Percentage(100)].as_ref())
-                    .split(f.size());
-                draw_weights(f, &mut weight_table, rects[0]);
+
+            // only y confirms deleting a task
+            Event::Input(input) if pending_delete.is_some() => {
+                if let Some(old_assign) = pending_delete.take() {
+                    if input == Key::Char('y') {
+                        rkm.delete_task(&old_assign)?;
+                    }
+                }
             }
-            _ => {
-                let chunks = Layout::default()
-                    .direction(Direction::Vertical)
-                    .constraints(
-                        [
-                            Constraint::Percentage(30),
-                            Constraint::Percentage(50),
-                            Constraint::Percentage(20),
-                        ]
-                        .as_ref(),
-                    )
-                    .split(f.size());
-                let mini_chunks = Layout::default()
-                    .direction(Direction::Horizontal)
-                    .constraints([Constraint::Percentage(75), Constraint::Percentage(25)].as_ref())
-                    .split(chunks[0]);
-                // draw_gauge(&mut f, &app, chunks[2]);
-                draw_gauge(f, &app, chunks[2]);
-                draw_task_table(f, &mut app, chunks[1]);
-                draw_current_task(f, &app, mini_chunks[0]);
-                draw_tag_counter(f, &app, mini_chunks[1]);
+
+            // any key closes the explanation
+            Event::Input(_) if why_rows.is_some() => {
+                why_rows = None;
             }
-        })?;
 
-        // keybindings
-        match events.next()? {
             Event::Input(input) => match input {
-                // denote the currently selected task as complete and reroll a new one
-                Key::Char('c') => {
-                    if its_task_time && !app.paused {
-                        let mut fin_task_tag = app.current_task[0].clone();
-                        fin_task_tag.pop();
-                        *tag_ctr.get_mut(&fin_task_tag).unwrap() += 1;
-                        app.completed = convert_hashmap_to_tuplevector(&tag_ctr, &tags);
-                        let (curr_task, items_to_list, weighttable_vec) =
-                            choose_task(&task_path, &tags, &initial_tag_weights, &use_due_dates);
-                        posttask_utils::nextupnotif(&curr_task[1])?;
-                        weight_table = WeightTable::new(weighttable_vec);
-                        app.current_task = curr_task;
-                        app.items = items_to_list;
-                    } else {
+                // denote the currently selected task as complete, write that back
+                // to the task file and reroll a new one. a recurring task
+                // comes back with its next due date
+                Key::Char('c') if rkm.session.is_working() => rkm.complete_task()?,
+
+                // reroll the currently selected task without marking current task as complete
+                Key::Char('r') if rkm.session.is_working() => {
+                    rkm.locked = None;
+                    rkm.end_pomodoro(Outcome::Rerolled)?;
+                    if let Err(e) = rkm.reroll() {
+                        rkm.app.status = Some(e.to_string());
                     }
+                    rkm.snapshot()?;
                 }
 
-                // reroll the currently selected task without marking current task as complete
-                Key::Char('r') => {
-                    if its_task_time && !app.paused {
-                        let (curr_task, items_to_list, weighttable_vec) =
-                            choose_task(&task_path, &tags, &initial_tag_weights, &use_due_dates);
-                        posttask_utils::nextupnotif(&curr_task[1])?;
-                        weight_table = WeightTable::new(weighttable_vec);
-                        app.current_task = curr_task;
-                        app.items = items_to_list;
-                    } else {
+                // add a new task to the task file
+                Key::Char('a') if curr_screen == "tasks" => {
+                    task_form = Some(TaskForm::add(&rkm.config.tags));
+                }
+
+                // edit the highlighted task
+                Key::Char('e') if curr_screen == "tasks" => {
+                    if let Some(assign) = rkm.app.selected() {
+                        task_form = Some(TaskForm::edit(&rkm.config.tags, assign));
+                    }
+                }
+
+                // delete the highlighted task, after asking
+                Key::Char('d') if curr_screen == "tasks" => {
+                    pending_delete = rkm.app.selected().cloned();
+                }
+
+                // work on the highlighted task instead of rolling one
+                Key::Char('\n') if curr_screen == "tasks" => {
+                    if let Some(assign) = rkm.app.selected().cloned() {
+                        rkm.pick_by_hand(assign)?;
                     }
                 }
 
+                // explain why the current task came up
+                Key::Char('y') if curr_screen == "tasks" => match rkm.explain_current() {
+                    Ok(rows) => why_rows = Some(rows),
+                    Err(e) => rkm.app.status = Some(e.to_string()),
+                },
+
+                // switch to the next way of picking tasks
+                Key::Char('m') => {
+                    let strategy = next_strategy(rkm.picker.strategy_name());
+                    rkm.set_strategy(strategy);
+                }
+
                 // fast forward timer to the end
                 Key::Char('f') => {
-                    app.progress = 1.0;
+                    if let Some(pomodoro) = rkm.curr_pomodoro.as_mut() {
+                        pomodoro.skip();
+                    }
+                    rkm.app.timer.fast_forward();
                 }
 
                 // rewind timer to beginning
                 Key::Char('0') => {
-                    app.progress = 0.0;
+                    rkm.app.timer.rewind();
                 }
 
                 // QUIT
                 Key::Char('q') => {
+                    rkm.end_pomodoro(Outcome::Interrupted)?;
+                    rkm.snapshot()?;
                     break;
                 }
 
                 // pause rkm
                 Key::Char('p') => {
-                    if app.paused {
-                        app.paused = false;
-                        app.current_task.pop();
-                    } else {
-                        app.paused = true;
-                        app.current_task.push("PAUSED".to_string());
-                    }
+                    let transition = rkm.session.toggle_pause();
+                    rkm.apply(transition)?;
                 }
 
                 // move cursor down or up on task table
@@ -285,10 +1308,10 @@ fn main() -> Result<(), Box<dyn Error>> {
                         help_table.next();
                     }
                     "stats" => {
-                        weight_table.next();
+                        rkm.weight_table.next();
                     }
                     "tasks" => {
-                        app.next();
+                        rkm.app.next();
                     }
                     _ => {}
                 },
@@ -298,10 +1321,10 @@ fn main() -> Result<(), Box<dyn Error>> {
                         help_table.previous();
                     }
                     "stats" => {
-                        weight_table.previous();
+                        rkm.weight_table.previous();
                     }
                     "tasks" => {
-                        app.previous();
+                        rkm.app.previous();
                     }
                     _ => {}
                 },
@@ -322,78 +1345,65 @@ fn main() -> Result<(), Box<dyn Error>> {
                         curr_screen = String::from("tasks");
                     }
                     _ => {
+                        // weights may have moved on to another rule since the last roll
+                        rkm.refresh_tasks();
                         curr_screen = String::from("stats");
                     }
                 },
 
+                // toggle history charts
+                Key::Char('g') => match curr_screen.as_str() {
+                    "history" => {
+                        curr_screen = String::from("tasks");
+                    }
+                    _ => {
+                        history_stats = compute_history_stats(
+                            &rkm.history.load()?,
+                            &rkm.config.tags,
+                            &rkm.config.weekday_weights,
+                            history_days,
+                        );
+                        curr_screen = String::from("history");
+                    }
+                },
+
+                // switch history charts between the last week and month
+                Key::Char('w') if curr_screen == "history" => {
+                    history_days = if history_days == 7 { 30 } else { 7 };
+                    history_stats = compute_history_stats(
+                        &rkm.history.load()?,
+                        &rkm.config.tags,
+                        &rkm.config.weekday_weights,
+                        history_days,
+                    );
+                }
+
                 _ => {}
             },
 
-            // what is done every 250 ms?
-            Event::Tick => {
-                // if app is paused do nothing.
-                if app.paused {
-
-                    // is it time for a task?
-                } else if its_task_time {
-                    // is next break a long break?
-                    if min_break_ctr == maxno_min_breaks {
-                        its_max_break_time = app.update(task_time);
-                        //posttask_utils::nextupnotif("Long Chill!")?;
-                    } else {
-                        // otherwise have a small break
-                        its_min_break_time = app.update(task_time);
-                        //posttask_utils::nextupnotif("Short Chill!")?;
-                    }
-
-                    // if task time is up. reset task time. increment tag counter
-                    if its_min_break_time || its_max_break_time {
-                        posttask_utils::playsound(&sound_path, &sink)?;
-                        posttask_utils::finishnotif()?;
-                        let mut fin_task_tag = app.current_task[0].clone();
-                        fin_task_tag.pop();
-                        *tag_ctr.get_mut(&fin_task_tag).unwrap() += 1;
-                        app.completed = convert_hashmap_to_tuplevector(&tag_ctr, &tags);
-                        its_task_time = false;
-                    };
-
-                // time for a small break?
-                } else if its_min_break_time {
-                    app.current_task = vec![String::from("TAKE A CHILL PILL\n")];
-                    its_task_time = app.update(min_break_time);
-
-                    // if small break over, reroll task
-                    if its_task_time {
-                        posttask_utils::playsound(&sound_path, &sink)?;
-                        posttask_utils::finishnotif()?;
-                        min_break_ctr += 1;
-                        let (curr_task, items_to_list, weighttable_vec) =
-                            choose_task(&task_path, &tags, &initial_tag_weights, &use_due_dates);
-                        posttask_utils::nextupnotif(&curr_task[1])?;
-                        weight_table = WeightTable::new(weighttable_vec);
-                        app.current_task = curr_task;
-                        app.items = items_to_list;
-                        its_min_break_time = false;
-                    }
+            // the task file or config was changed on disk
+            Event::Changed(path) if rkm.is_news(&path) => {
+                if path == rkm.config.task_path {
+                    rkm.refresh_tasks();
+                } else {
+                    rkm.reload_config(&config_path);
+                    rkm.watch_files(&mut events, &config_path);
+                }
+            }
+            Event::Changed(_) => {}
 
-                // time for big break?
-                } else if its_max_break_time {
-                    app.current_task = vec![String::from("TAKE A LOONG CHILL PILL\n")];
-                    its_task_time = app.update(max_break_time);
-
-                    // if big break over, reroll task
-                    if its_task_time {
-                        posttask_utils::playsound(&sound_path, &sink)?;
-                        posttask_utils::finishnotif()?;
-                        min_break_ctr = 0;
-                        let (curr_task, items_to_list, weighttable_vec) =
-                            choose_task(&task_path, &tags, &initial_tag_weights, &use_due_dates);
-                        posttask_utils::nextupnotif(&curr_task[1])?;
-                        weight_table = WeightTable::new(weighttable_vec);
-                        app.current_task = curr_task;
-                        app.items = items_to_list;
-                        its_max_break_time = false;
-                    }
+            // what is done on every tick? the timer runs off the wall
+            // clock so ticks only decide how often it is checked.
+            // when it runs out the session moves on to its next phase
+            Event::Tick => {
+                rkm.roll_over_day()?;
+                if !rkm.session.is_paused() && rkm.app.update() {
+                    let transition = rkm.session.advance();
+                    rkm.apply(transition)?;
+                } else if Local::now().signed_duration_since(rkm.last_snapshot)
+                    >= chrono::Duration::seconds(SNAPSHOT_INTERVAL_SECS)
+                {
+                    rkm.snapshot()?;
                 }
             }
         };
@@ -1,35 +1,37 @@
+use super::errors::RkmError;
 use notify_rust::Notification;
-use rodio::decoder::DecoderError;
 use rodio::Sink;
-use std::error::Error;
 use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
 
 // Play a given sound given a file path to that sound and
 // a preexisting rodio sink
-pub fn playsound(filepath: &Path, sink: &Sink) -> Result<(), DecoderError> {
-    let file = File::open(filepath).unwrap();
-    let source = rodio::Decoder::new(BufReader::new(file))?;
+pub fn playsound(filepath: &Path, sink: &Sink) -> Result<(), RkmError> {
+    let file = File::open(filepath).map_err(RkmError::file(filepath))?;
+    let source = rodio::Decoder::new(BufReader::new(file))
+        .map_err(|e| RkmError::Audio(format!("{}: {}", filepath.display(), e)))?;
     sink.append(source);
     sink.play();
     Ok(())
 }
 
-pub fn finishnotif() -> Result<(), Box<dyn Error>> {
+pub fn finishnotif() -> Result<(), RkmError> {
     Notification::new()
         .summary("Rusty-Krab-Manager")
         .body("Time is up!")
         .icon("clock")
-        .show()?;
+        .show()
+        .map_err(|e| RkmError::Notification(e.to_string()))?;
     Ok(())
 }
 
-pub fn nextupnotif(nexttask: &str) -> Result<(), Box<dyn Error>> {
+pub fn nextupnotif(nexttask: &str) -> Result<(), RkmError> {
     Notification::new()
         .summary("Rusty-Krab-Manager")
         .body(&format!("Now work on: {}", nexttask))
         .icon("clock")
-        .show()?;
+        .show()
+        .map_err(|e| RkmError::Notification(e.to_string()))?;
     Ok(())
 }
@@ -0,0 +1,229 @@
+use super::errors::RkmError;
+use config::Value;
+use std::collections::HashMap;
+
+// THESE ARE ALL FUNCTIONS RELATED TO STEERING
+// THE DAY TOWARDS A TARGET PER TAG
+//
+
+// How tag weights are balanced against what was done today
+#[derive(Clone, PartialEq)]
+pub enum QuotaMode {
+    // the [weights] of the day are used as they are
+    Off,
+    // each tag aims for a number of pomodoros a day, in tag
+    // order. tags without a quota are weighted as usual
+    Count(Vec<Option<i64>>),
+    // each tag aims for its share of the [weights] of the day
+    Share,
+}
+
+impl QuotaMode {
+    // the quota of a tag, if it has one
+    pub fn quota(&self, tag_idx: usize) -> Option<i64> {
+        match self {
+            QuotaMode::Count(quotas) => quotas[tag_idx],
+            _ => None,
+        }
+    }
+
+    // Scale the tag weights by how far each tag lags its target.
+    // A tag that met its target is not rolled any more. Once every
+    // target is met the weights are left as they are.
+    pub fn balance_tag_weights(
+        &self,
+        tag_weights: &[f64],
+        tags: &[String],
+        tag_ctr: &HashMap<String, i64>,
+    ) -> Vec<f64> {
+        let done = |tag: &String| *tag_ctr.get(tag).unwrap_or(&0) as f64;
+        let balanced: Vec<f64> = match self {
+            QuotaMode::Off => return tag_weights.to_vec(),
+            QuotaMode::Count(quotas) => tag_weights
+                .iter()
+                .zip(tags)
+                .zip(quotas)
+                .map(|((weight, tag), quota)| match quota {
+                    Some(quota) if *quota > 0 => {
+                        let left = (*quota as f64 - done(tag)).max(0.0);
+                        weight * left / *quota as f64
+                    }
+                    Some(_) => 0.0,
+                    None => *weight,
+                })
+                .collect(),
+            QuotaMode::Share => {
+                // what each tag should be at after the next pomodoro
+                let total = tags.iter().map(done).sum::<f64>() + 1.0;
+                tag_weights
+                    .iter()
+                    .zip(tags)
+                    .map(|(weight, tag)| {
+                        if *weight > 0.0 {
+                            (weight * total - done(tag)).max(0.0)
+                        } else {
+                            0.0
+                        }
+                    })
+                    .collect()
+            }
+        };
+
+        let sum: f64 = balanced.iter().sum();
+        if sum > 0.0 {
+            balanced.iter().map(|weight| weight / sum).collect()
+        } else {
+            tag_weights.to_vec()
+        }
+    }
+}
+
+// Read quota_mode and the [quotas] table of the config
+pub fn parse_quotas(
+    mode: &str,
+    table: HashMap<String, Value>,
+    tags: &[String],
+) -> Result<QuotaMode, RkmError> {
+    let bad_quota = |tag: &str, reason: &str| RkmError::BadQuota {
+        tag: tag.to_string(),
+        reason: reason.to_string(),
+    };
+    match mode {
+        "off" => Ok(QuotaMode::Off),
+        "share" => Ok(QuotaMode::Share),
+        "count" => {
+            let mut quotas = vec![None; tags.len()];
+            for (tag, value) in table {
+                let tag_idx = tags
+                    .iter()
+                    .position(|known| *known == tag)
+                    .ok_or_else(|| bad_quota(&tag, "not one of the tags"))?;
+                let quota = value
+                    .into_int()
+                    .map_err(|_| bad_quota(&tag, "is not a whole number"))?;
+                if quota < 0 {
+                    return Err(bad_quota(&tag, "can not be negative"));
+                }
+                quotas[tag_idx] = Some(quota);
+            }
+            Ok(QuotaMode::Count(quotas))
+        }
+        _ => Err(RkmError::BadValue {
+            key: "quota_mode",
+            reason: format!("is '{}'. expected one of: off, count, share", mode),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history_utils::{tag_counts_on, Outcome, PomodoroRecord};
+    use chrono::prelude::*;
+    use chrono::Duration;
+
+    fn assert_close(found: &[f64], expected: &[f64]) {
+        assert_eq!(found.len(), expected.len());
+        for (found, expected) in found.iter().zip(expected) {
+            assert!(
+                (found - expected).abs() < 1e-9,
+                "{:?} != {:?}",
+                found,
+                expected
+            );
+        }
+    }
+
+    fn tags() -> Vec<String> {
+        vec!["school".to_string(), "job".to_string()]
+    }
+
+    fn counts(school: i64, job: i64) -> HashMap<String, i64> {
+        tags().into_iter().zip(vec![school, job]).collect()
+    }
+
+    #[test]
+    fn count_quota_weighs_tags_by_what_is_left() {
+        let mode = QuotaMode::Count(vec![Some(4), Some(2)]);
+        let balance = |ctr| mode.balance_tag_weights(&[0.5, 0.5], &tags(), &ctr);
+        assert_close(&balance(counts(0, 0)), &[0.5, 0.5]);
+        // half of school is done, none of job
+        assert_close(&balance(counts(2, 0)), &[1.0 / 3.0, 2.0 / 3.0]);
+        // job is done for today
+        assert_close(&balance(counts(2, 2)), &[1.0, 0.0]);
+        // once every quota is met the weights are used as they are
+        assert_close(&balance(counts(4, 3)), &[0.5, 0.5]);
+    }
+
+    #[test]
+    fn tags_without_a_quota_keep_their_weight() {
+        let mode = QuotaMode::Count(vec![Some(2), None]);
+        let balanced = mode.balance_tag_weights(&[0.6, 0.4], &tags(), &counts(1, 5));
+        assert_close(&balanced, &[0.3 / 0.7, 0.4 / 0.7]);
+    }
+
+    #[test]
+    fn share_quota_catches_up_with_the_weights() {
+        let mode = QuotaMode::Share;
+        let balance = |ctr| mode.balance_tag_weights(&[0.75, 0.25], &tags(), &ctr);
+        assert_close(&balance(counts(0, 0)), &[0.75, 0.25]);
+        // school is ahead of its share, so job is next
+        assert_close(&balance(counts(3, 0)), &[0.0, 1.0]);
+        assert_close(&balance(counts(3, 1)), &[0.75, 0.25]);
+        assert_close(
+            &QuotaMode::Off.balance_tag_weights(&[0.75, 0.25], &tags(), &counts(3, 0)),
+            &[0.75, 0.25],
+        );
+    }
+
+    #[test]
+    fn quotas_count_from_midnight() {
+        let today = Local.ymd(2030, 3, 4);
+        let pomodoro = |tag: &str, start: DateTime<Local>| PomodoroRecord {
+            start,
+            end: start + Duration::minutes(25),
+            tag: tag.to_string(),
+            task: String::from("task"),
+            outcome: Outcome::Finished,
+            paused_secs: 0,
+            manual: false,
+        };
+        let history = vec![
+            pomodoro("job", today.pred().and_hms(22, 0, 0)),
+            pomodoro("job", today.pred().and_hms(23, 30, 0)),
+            pomodoro("school", today.and_hms(0, 10, 0)),
+        ];
+        let mode = QuotaMode::Count(vec![Some(2), Some(2)]);
+
+        // job met its quota yesterday
+        let yesterday = tag_counts_on(&history, &tags(), today.pred());
+        assert_eq!(yesterday, counts(0, 2));
+        assert_close(
+            &mode.balance_tag_weights(&[0.5, 0.5], &tags(), &yesterday),
+            &[1.0, 0.0],
+        );
+        // and is rolled again the next day
+        let today = tag_counts_on(&history, &tags(), today);
+        assert_eq!(today, counts(1, 0));
+        assert_close(
+            &mode.balance_tag_weights(&[0.5, 0.5], &tags(), &today),
+            &[1.0 / 3.0, 2.0 / 3.0],
+        );
+    }
+
+    #[test]
+    fn quotas_must_be_for_known_tags() {
+        let table = |tag: &str, quota: i64| -> HashMap<String, Value> {
+            vec![(tag.to_string(), Value::from(quota))]
+                .into_iter()
+                .collect()
+        };
+        assert!(
+            parse_quotas("count", table("job", 3), &tags()).ok()
+                == Some(QuotaMode::Count(vec![None, Some(3)]))
+        );
+        assert!(parse_quotas("count", table("hobby", 3), &tags()).is_err());
+        assert!(parse_quotas("count", table("job", -1), &tags()).is_err());
+        assert!(parse_quotas("sometimes", HashMap::new(), &tags()).is_err());
+    }
+}
@@ -2,34 +2,193 @@
 // RANDOMNESS
 //
 
-use rand::Rng;
+use super::errors::RkmError;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+
+// how far off 1 the sum of a probability distribution may be,
+// relative to the sum. weights are normalized more than once
+// on the way to a roll and pick up rounding errors each time
+const SUM_TOLERANCE: f64 = 1e-9;
 
 // Take a probability distribution and
 // change it to a cumulative distribution
 // where the last element is always 1
-pub fn make_cdf(pdf: &[f64]) -> Vec<f64> {
-    let error_margin = f64::EPSILON;
+pub fn make_cdf(pdf: &[f64]) -> Result<Vec<f64>, RkmError> {
     let sum: f64 = pdf.iter().sum();
-    assert!(
-        (sum - 1.0).abs() <= error_margin,
-        "Probability distribution does not sum to 1! Instead sums to {}",
-        sum as f32
-    );
+    if pdf.is_empty() || !sum.is_finite() || (sum - 1.0).abs() > SUM_TOLERANCE * sum.max(1.0) {
+        return Err(RkmError::BadDistribution(format!(
+            "{} probabilities sum to {} instead of 1",
+            pdf.len(),
+            sum
+        )));
+    }
     let mut cdf: Vec<f64> = Vec::with_capacity(pdf.len());
-    cdf.push(pdf[0]);
-    for idx in 1..pdf.len() {
-        cdf.push(cdf[idx - 1] + pdf[idx]);
+    let mut running = 0.0;
+    for prob in pdf {
+        running += prob;
+        cdf.push(running);
+    }
+    // from the last side that can come up on the cdf is exactly
+    // 1, so every draw in [0, 1) lands on a side despite rounding
+    let last_possible = pdf.iter().rposition(|prob| *prob > 0.0).unwrap_or(0);
+    for cumulative in cdf.iter_mut().skip(last_possible) {
+        *cumulative = 1.0;
     }
-    cdf
+    Ok(cdf)
+}
+
+// Scale weights so they sum to 1. If they are all 0 (or too
+// small to add up) every element is equally likely instead
+pub fn normalize(weights: Vec<f64>) -> Vec<f64> {
+    let sum: f64 = weights.iter().sum();
+    if sum > 0.0 && sum.is_finite() {
+        weights.iter().map(|weight| weight / sum).collect()
+    } else {
+        vec![1.0 / weights.len() as f64; weights.len()]
+    }
+}
+
+// One roll of the die, kept so every pick can be explained
+// after the fact
+#[derive(Clone, Serialize)]
+pub struct DieRoll {
+    // what was rolled for, e.g. "tag" or "task"
+    pub label: String,
+    // the sides of the die and their probabilities
+    pub options: Vec<String>,
+    pub pdf: Vec<f64>,
+    // the uniform draw in [0, 1) and the side it landed on
+    pub draw: f64,
+    pub index: usize,
+}
+
+// Every random choice rkm makes goes through here. Given a seed
+// the same rolls come up in the same order.
+pub struct Dice {
+    rng: StdRng,
+    seed: Option<u64>,
+    rolls: Vec<DieRoll>,
 }
 
-// Given a probability distribution containing n elements
-// randomly roll a n-sided die weighted to the probabilities
-// given. Return the index of the side that comes up.
-pub fn roll_die(pdf: &[f64]) -> usize {
-    let mut rng = rand::thread_rng();
-    let x = rng.gen::<f64>();
-    let cdf = make_cdf(pdf);
-    let index = cdf.iter().position(|&r| x < r).unwrap();
-    index
+impl Dice {
+    pub fn new(seed: Option<u64>) -> Self {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Self {
+            rng,
+            seed,
+            rolls: vec![],
+        }
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    // Given a probability distribution containing n elements
+    // randomly roll a n-sided die weighted to the probabilities
+    // given. Return the index of the side that comes up.
+    pub fn roll_die(
+        &mut self,
+        label: &str,
+        options: Vec<String>,
+        pdf: &[f64],
+    ) -> Result<usize, RkmError> {
+        let x = self.rng.gen::<f64>();
+        let cdf = make_cdf(pdf)
+            .map_err(|e| RkmError::BadDistribution(format!("rolling for {}: {}", label, e)))?;
+        // the last cdf entry is 1 and x is below 1
+        let index = cdf.iter().position(|&r| x < r).unwrap_or(cdf.len() - 1);
+        self.rolls.push(DieRoll {
+            label: label.to_string(),
+            options,
+            pdf: pdf.to_vec(),
+            draw: x,
+            index,
+        });
+        Ok(index)
+    }
+
+    // the rolls made since the last call
+    pub fn take_rolls(&mut self) -> Vec<DieRoll> {
+        std::mem::take(&mut self.rolls)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the sides that come up on a number of rolls of the same die
+    fn roll_many(dice: &mut Dice, pdf: &[f64], count: usize) -> Vec<usize> {
+        (0..count)
+            .map(|_| dice.roll_die("test", vec![], pdf).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_rolls_the_same() {
+        let pdf = normalize(vec![3.0, 1.0, 0.5, 2.0]);
+        let first = roll_many(&mut Dice::new(Some(42)), &pdf, 50);
+        let second = roll_many(&mut Dice::new(Some(42)), &pdf, 50);
+        assert_eq!(first, second);
+        // a different seed gives different rolls
+        assert_ne!(first, roll_many(&mut Dice::new(Some(43)), &pdf, 50));
+    }
+
+    #[test]
+    fn rolls_are_kept_until_taken() {
+        let mut dice = Dice::new(Some(7));
+        let options = vec!["school".to_string(), "job".to_string()];
+        let index = dice.roll_die("tag", options, &[0.5, 0.5]).unwrap();
+        let rolls = dice.take_rolls();
+        assert_eq!(rolls.len(), 1);
+        assert_eq!(rolls[0].label, "tag");
+        assert_eq!(rolls[0].index, index);
+        assert!(dice.take_rolls().is_empty());
+    }
+
+    #[test]
+    fn rounding_errors_in_the_sum_are_allowed() {
+        // ten tenths add up to slightly less than 1
+        let pdf = vec![0.1; 10];
+        assert!(pdf.iter().sum::<f64>() != 1.0);
+        let cdf = make_cdf(&pdf).unwrap();
+        assert_eq!(cdf[9], 1.0);
+
+        // errors a lot bigger than the float epsilon still pass
+        assert!(make_cdf(&[0.5, 0.5 + 1e-12]).is_ok());
+        assert!(make_cdf(&[0.5, 0.5 - 1e-12]).is_ok());
+
+        // so do weights normalized more than once
+        let pdf = normalize(normalize(vec![1.0 / 3.0, 1e-7, 2.0 / 3.0, 1e-12]));
+        assert_eq!(*make_cdf(&pdf).unwrap().last().unwrap(), 1.0);
+    }
+
+    #[test]
+    fn distributions_that_do_not_sum_to_1_are_refused() {
+        assert!(make_cdf(&[0.5, 0.4]).is_err());
+        assert!(make_cdf(&[0.5, 0.6]).is_err());
+        assert!(make_cdf(&[]).is_err());
+        assert!(make_cdf(&[f64::NAN, 1.0]).is_err());
+        assert!(Dice::new(Some(1)).roll_die("tag", vec![], &[0.2]).is_err());
+    }
+
+    #[test]
+    fn sides_without_probability_never_come_up() {
+        let pdf = [0.0, 0.5, 0.5, 0.0];
+        let cdf = make_cdf(&pdf).unwrap();
+        assert_eq!(cdf, vec![0.0, 0.5, 1.0, 1.0]);
+        let rolls = roll_many(&mut Dice::new(Some(3)), &pdf, 200);
+        assert!(rolls.iter().all(|&index| index == 1 || index == 2));
+    }
+
+    #[test]
+    fn all_zero_weights_are_equally_likely() {
+        assert_eq!(normalize(vec![0.0; 4]), vec![0.25; 4]);
+    }
 }
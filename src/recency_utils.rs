@@ -0,0 +1,234 @@
+use super::assignment_utils::Assignment;
+use super::history_utils::{Outcome, PomodoroRecord};
+use super::rand_utils::normalize;
+use chrono::prelude::*;
+use std::collections::HashMap;
+
+// THESE ARE ALL FUNCTIONS RELATED TO WEIGHING
+// TASKS BY WHEN THEY WERE LAST WORKED ON
+//
+
+// Makes tags and tasks that have not been worked on in a while
+// more likely. Built from the history before every roll.
+pub struct RecencyBoost {
+    // weight multiplier of something not worked on for `hours`
+    boost: f64,
+    hours: f64,
+    now: DateTime<Local>,
+    last_tag: HashMap<String, DateTime<Local>>,
+    last_task: HashMap<(String, String), DateTime<Local>>,
+}
+
+impl RecencyBoost {
+    pub fn new(history: &[PomodoroRecord], boost: f64, hours: f64, now: DateTime<Local>) -> Self {
+        let mut last_tag = HashMap::new();
+        let mut last_task = HashMap::new();
+        // a reroll is not time spent on the task
+        for record in history
+            .iter()
+            .filter(|record| record.outcome != Outcome::Rerolled)
+        {
+            let tag_end = last_tag.entry(record.tag.clone()).or_insert(record.end);
+            *tag_end = record.end.max(*tag_end);
+            let task_end = last_task
+                .entry((record.tag.clone(), record.task.clone()))
+                .or_insert(record.end);
+            *task_end = record.end.max(*task_end);
+        }
+        Self {
+            boost,
+            hours,
+            now,
+            last_tag,
+            last_task,
+        }
+    }
+
+    // grows linearly from 1 right after something was worked on
+    // to `boost` after `hours`. never worked on counts as the full boost
+    fn multiplier(&self, last_worked: Option<&DateTime<Local>>) -> f64 {
+        if self.boost <= 1.0 {
+            return 1.0;
+        }
+        let hours_since = match last_worked {
+            Some(last_worked) => {
+                self.now.signed_duration_since(*last_worked).num_minutes() as f64 / 60.0
+            }
+            None => self.hours,
+        };
+        1.0 + (self.boost - 1.0) * (hours_since / self.hours).clamp(0.0, 1.0)
+    }
+
+    fn task_multiplier(&self, assign: &Assignment) -> f64 {
+        self.multiplier(
+            self.last_task
+                .get(&(assign.tag.clone(), assign.name.clone())),
+        )
+    }
+
+    // Boost the tag weights left after update_tagweights and
+    // make them sum to 1 again. Tags at 0 stay at 0.
+    pub fn boost_tag_weights(&self, tag_weights: &[f64], tags: &[String]) -> Vec<f64> {
+        let boosted = tag_weights
+            .iter()
+            .zip(tags)
+            .map(|(weight, tag)| weight * self.multiplier(self.last_tag.get(tag)))
+            .collect();
+        normalize(boosted)
+    }
+
+    // Boost the task weights of a tag and make them sum to 1 again
+    pub fn boost_task_pdf(&self, pdf: Vec<f64>, assign: &[Assignment]) -> Vec<f64> {
+        let boosted = pdf
+            .iter()
+            .zip(assign)
+            .map(|(prob, assign)| prob * self.task_multiplier(assign))
+            .collect();
+        normalize(boosted)
+    }
+}
+
+// Take the tasks served in the last `window` pomodoros out of
+// the running, unless that would leave nothing to pick from
+pub fn drop_recent_tasks(
+    tag_to_vector_map: &mut HashMap<String, Vec<Assignment>>,
+    history: &[PomodoroRecord],
+    window: usize,
+) {
+    let recent: Vec<&PomodoroRecord> = history.iter().rev().take(window).collect();
+    let is_recent = |assign: &Assignment| {
+        recent
+            .iter()
+            .any(|record| record.tag == assign.tag && record.task == assign.name)
+    };
+    let any_left = tag_to_vector_map
+        .values()
+        .flatten()
+        .any(|assign| !is_recent(assign));
+    if any_left {
+        for assign_vec in tag_to_vector_map.values_mut() {
+            assign_vec.retain(|assign| !is_recent(assign));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assignment_utils::update_tagweights;
+    use chrono::Duration;
+    use std::str::FromStr;
+
+    fn now() -> DateTime<Local> {
+        Local.ymd(2030, 3, 4).and_hms(12, 0, 0)
+    }
+
+    fn tags() -> Vec<String> {
+        vec!["school".to_string(), "job".to_string()]
+    }
+
+    fn tasks(lines: &[&str]) -> HashMap<String, Vec<Assignment>> {
+        let mut tasks: HashMap<String, Vec<Assignment>> = HashMap::new();
+        for line in lines {
+            let assign = Assignment::from_str(line).unwrap();
+            tasks.entry(assign.tag.clone()).or_default().push(assign);
+        }
+        tasks
+    }
+
+    fn names(tasks: &HashMap<String, Vec<Assignment>>) -> Vec<String> {
+        let mut names: Vec<String> = tasks.values().flatten().map(|a| a.name.clone()).collect();
+        names.sort();
+        names
+    }
+
+    // pomodoros on the given tasks, the last one ending at `now`
+    fn history(worked: &[(&str, &str)]) -> Vec<PomodoroRecord> {
+        let count = worked.len() as i32;
+        worked
+            .iter()
+            .zip(1..)
+            .map(|((tag, task), idx)| {
+                let end = now() - Duration::hours((count - idx).into());
+                PomodoroRecord {
+                    start: end - Duration::minutes(25),
+                    end,
+                    tag: tag.to_string(),
+                    task: task.to_string(),
+                    outcome: Outcome::Finished,
+                    paused_secs: 0,
+                    manual: false,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tasks_in_the_window_sit_out() {
+        let history = history(&[("school", "Essay"), ("job", "Report"), ("school", "Essay")]);
+        let all = tasks(&["school, Essay", "school, Reading", "job, Report"]);
+
+        let mut candidates = all.clone();
+        drop_recent_tasks(&mut candidates, &history, 1);
+        assert_eq!(names(&candidates), vec!["Reading", "Report"]);
+
+        let mut candidates = all.clone();
+        drop_recent_tasks(&mut candidates, &history, 2);
+        assert_eq!(names(&candidates), vec!["Reading"]);
+
+        // a window of 0 keeps every task
+        let mut candidates = all;
+        drop_recent_tasks(&mut candidates, &history, 0);
+        assert_eq!(names(&candidates), vec!["Essay", "Reading", "Report"]);
+    }
+
+    #[test]
+    fn every_task_is_kept_if_all_were_recent() {
+        let history = history(&[("school", "Essay"), ("job", "Report")]);
+        let mut candidates = tasks(&["school, Essay", "job, Report"]);
+        drop_recent_tasks(&mut candidates, &history, 5);
+        assert_eq!(names(&candidates), vec!["Essay", "Report"]);
+    }
+
+    #[test]
+    fn tag_emptied_by_the_window_gives_up_its_weight() {
+        let history = history(&[("school", "Essay")]);
+        let mut candidates = tasks(&["school, Essay", "job, Report"]);
+        drop_recent_tasks(&mut candidates, &history, 1);
+        assert!(candidates["school"].is_empty());
+        assert_eq!(
+            update_tagweights(&candidates, &[0.7, 0.3], &tags()),
+            vec![0.0, 1.0]
+        );
+        // also when the tags left had no weight of their own
+        assert_eq!(
+            update_tagweights(&candidates, &[1.0, 0.0], &tags()),
+            vec![0.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn boost_grows_with_time_since_last_worked() {
+        let history = history(&[("job", "Report"), ("school", "Essay")]);
+        // job was last worked on an hour ago, school just now
+        let recency = RecencyBoost::new(&history, 3.0, 2.0, now());
+        let boosted = recency.boost_tag_weights(&[0.5, 0.5], &tags());
+        assert!((boosted[0] - 1.0 / 3.0).abs() < 1e-9);
+        assert!((boosted[1] - 2.0 / 3.0).abs() < 1e-9);
+
+        // a task never worked on gets the full boost
+        let assign = tasks(&["school, Essay", "school, Reading"])
+            .remove("school")
+            .unwrap();
+        let pdf = recency.boost_task_pdf(vec![0.5, 0.5], &assign);
+        assert!((pdf[0] - 0.25).abs() < 1e-9);
+        assert!((pdf[1] - 0.75).abs() < 1e-9);
+
+        // no boost leaves the weights as they are
+        let recency = RecencyBoost::new(&history, 1.0, 2.0, now());
+        assert_eq!(
+            recency.boost_tag_weights(&[0.5, 0.5], &tags()),
+            vec![0.5, 0.5]
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use super::errors::RkmError;
+use super::settings_util::WEEKDAYS;
+use chrono::prelude::*;
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+// THESE ARE ALL FUNCTIONS RELATED TO TASKS
+// THAT COME BACK AFTER THEY ARE DONE
+//
+
+// How often a task repeats, written after the due date of a
+// task as @daily, @weekly, @weekly mon thu, @every 3d or @monthly
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub enum Recurrence {
+    Daily,
+    // weekdays counted from monday. none means the weekday
+    // of the due date
+    Weekly(Vec<usize>),
+    EveryDays(i64),
+    // the same day of the month, or the last day of months
+    // that are too short
+    Monthly,
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "@daily"),
+            Recurrence::Weekly(days) if days.is_empty() => write!(f, "@weekly"),
+            Recurrence::Weekly(days) => write!(
+                f,
+                "@weekly {}",
+                days.iter()
+                    .map(|day| WEEKDAYS[*day])
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+            Recurrence::EveryDays(days) => write!(f, "@every {}d", days),
+            Recurrence::Monthly => write!(f, "@monthly"),
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = RkmError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad_rule = |reason: String| RkmError::BadRecurrence {
+            rule: s.to_string(),
+            reason,
+        };
+        let mut words = s
+            .strip_prefix('@')
+            .ok_or_else(|| bad_rule(String::from("does not start with @")))?
+            .split_whitespace();
+        let kind = words.next().unwrap_or_default().to_lowercase();
+        let args: Vec<String> = words.map(|word| word.to_lowercase()).collect();
+
+        match (kind.as_str(), args.as_slice()) {
+            ("daily", []) => Ok(Recurrence::Daily),
+            ("monthly", []) => Ok(Recurrence::Monthly),
+            ("weekly", days) => {
+                let mut days = days
+                    .iter()
+                    .map(|day| {
+                        WEEKDAYS
+                            .iter()
+                            .position(|weekday| weekday == day)
+                            .ok_or_else(|| {
+                                bad_rule(format!("'{}' is not one of {}", day, WEEKDAYS.join(", ")))
+                            })
+                    })
+                    .collect::<Result<Vec<usize>, _>>()?;
+                days.sort_unstable();
+                days.dedup();
+                Ok(Recurrence::Weekly(days))
+            }
+            ("every", [interval]) => {
+                // 3d or 2w
+                let (count, unit) = interval.split_at(interval.len().saturating_sub(1));
+                let days = match (count.parse::<i64>(), unit) {
+                    (Ok(count), "d") if count > 0 => count,
+                    (Ok(count), "w") if count > 0 => count * 7,
+                    _ => {
+                        return Err(bad_rule(format!(
+                            "'{}' is not a number of days or weeks like 3d or 2w",
+                            interval
+                        )))
+                    }
+                };
+                Ok(Recurrence::EveryDays(days))
+            }
+            ("daily", _) | ("monthly", _) | ("every", _) => Err(bad_rule(format!(
+                "@{} takes {}",
+                kind,
+                if kind == "every" {
+                    "one interval like 3d"
+                } else {
+                    "nothing after it"
+                }
+            ))),
+            _ => Err(bad_rule(String::from(
+                "expected @daily, @weekly, @weekly mon thu, @every 3d or @monthly",
+            ))),
+        }
+    }
+}
+
+// the given date moved by a number of months, on the same day or
+// the last day of the month if it is too short
+fn add_months(date: NaiveDate, months: i32) -> NaiveDate {
+    let month0 = date.month0() as i32 + months;
+    let year = date.year() + month0.div_euclid(12);
+    let month = month0.rem_euclid(12) as u32 + 1;
+    (1..=date.day())
+        .rev()
+        .find_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+        .unwrap_or(date)
+}
+
+impl Recurrence {
+    // the occurrence right after the given due date
+    fn step(&self, due: NaiveDateTime, first_due: NaiveDateTime, steps: i32) -> NaiveDateTime {
+        match self {
+            Recurrence::Daily => due + Duration::days(1),
+            Recurrence::EveryDays(days) => due + Duration::days(*days),
+            Recurrence::Weekly(days) if days.is_empty() => due + Duration::weeks(1),
+            Recurrence::Weekly(days) => (1..=7)
+                .map(|ahead| due + Duration::days(ahead))
+                .find(|next| days.contains(&(next.weekday().num_days_from_monday() as usize)))
+                .unwrap_or(due + Duration::weeks(1)),
+            // counted from the first due date so a task due on
+            // the 31st goes back to the 31st after february
+            Recurrence::Monthly => add_months(first_due.date(), steps).and_time(first_due.time()),
+        }
+    }
+
+    // The due date of the next occurrence: one step after the given
+    // due date, skipping any that are already past by now
+    pub fn next_due(&self, due: NaiveDateTime, now: NaiveDateTime) -> NaiveDateTime {
+        let mut next = due;
+        let mut steps = 0;
+        while next <= due || next <= now {
+            steps += 1;
+            next = self.step(next, due, steps);
+        }
+        next
+    }
+
+    // e.g. "every week on mon, thu"
+    pub fn describe(&self) -> String {
+        match self {
+            Recurrence::Daily => String::from("every day"),
+            Recurrence::Weekly(days) if days.is_empty() => String::from("every week"),
+            Recurrence::Weekly(days) => format!(
+                "every week on {}",
+                days.iter()
+                    .map(|day| WEEKDAYS[*day])
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Recurrence::EveryDays(days) => format!("every {} days", days),
+            Recurrence::Monthly => String::from("every month"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn next(rule: &str, due: &str, now: &str) -> String {
+        let recurrence: Recurrence = rule.parse().unwrap();
+        recurrence
+            .next_due(at(due), at(now))
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    }
+
+    #[test]
+    fn rules_read_back_the_way_they_are_written() {
+        for (rule, written) in &[
+            ("@daily", "@daily"),
+            ("@Weekly", "@weekly"),
+            ("@weekly thu mon thu", "@weekly mon thu"),
+            ("@every 3d", "@every 3d"),
+            ("@every 2w", "@every 14d"),
+            ("@monthly", "@monthly"),
+        ] {
+            let recurrence: Recurrence = rule.parse().unwrap();
+            assert_eq!(recurrence.to_string(), *written);
+        }
+        for rule in &[
+            "daily",
+            "@yearly",
+            "@daily 2",
+            "@every",
+            "@every 0d",
+            "@weekly moon",
+        ] {
+            assert!(rule.parse::<Recurrence>().is_err(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn weekly_on_given_days() {
+        // a monday to the thursday after and on to the next monday
+        assert_eq!(
+            next("@weekly mon thu", "2026-10-12 09:00", "2026-10-12 10:00"),
+            "2026-10-15 09:00"
+        );
+        assert_eq!(
+            next("@weekly mon thu", "2026-10-15 09:00", "2026-10-15 10:00"),
+            "2026-10-19 09:00"
+        );
+        // without days the weekday of the due date
+        assert_eq!(
+            next("@weekly", "2026-10-14 09:00", "2026-10-14 10:00"),
+            "2026-10-21 09:00"
+        );
+    }
+
+    #[test]
+    fn monthly_stays_on_the_end_of_short_months() {
+        assert_eq!(
+            next("@monthly", "2026-01-31 09:00", "2026-01-31 10:00"),
+            "2026-02-28 09:00"
+        );
+        assert_eq!(
+            next("@monthly", "2028-01-31 09:00", "2028-01-31 10:00"),
+            "2028-02-29 09:00"
+        );
+        // back to the 31st once february is skipped
+        assert_eq!(
+            next("@monthly", "2026-01-31 09:00", "2026-03-01 10:00"),
+            "2026-03-31 09:00"
+        );
+        assert_eq!(
+            next("@monthly", "2026-12-15 09:00", "2026-12-15 10:00"),
+            "2027-01-15 09:00"
+        );
+    }
+
+    #[test]
+    fn missed_occurrences_are_skipped() {
+        assert_eq!(
+            next("@daily", "2026-10-10 09:00", "2026-10-14 10:00"),
+            "2026-10-15 09:00"
+        );
+        assert_eq!(
+            next("@every 3d", "2026-10-01 09:00", "2026-10-08 12:00"),
+            "2026-10-10 09:00"
+        );
+        // not yet due still moves on by one step
+        assert_eq!(
+            next("@daily", "2026-10-20 09:00", "2026-10-14 10:00"),
+            "2026-10-21 09:00"
+        );
+    }
+}
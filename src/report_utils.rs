@@ -0,0 +1,106 @@
+use super::assignment_utils::{readin_all_tasks, Assignment};
+use super::errors::RkmError;
+use super::history_utils::{task_actuals, HistoryLog};
+use super::settings_util::parse_settings;
+use std::collections::HashMap;
+
+// THESE ARE ALL FUNCTIONS RELATED TO THE 'report'
+// SUBCOMMAND
+//
+
+// estimates against actuals of the done tasks of one tag
+#[derive(Default)]
+struct TagAccuracy {
+    tasks: usize,
+    estimated: i64,
+    actual: i64,
+    // tasks done in no more pomodoros than estimated
+    within: usize,
+}
+
+// Compare the pomodoro estimates of the tasks marked as done with
+// the pomodoros the history says they took, per tag, and list the
+// open tasks that already took more than estimated. Each occurrence
+// of a repeating task is counted on its own.
+pub fn run_report(config_path: &str) -> Result<(), RkmError> {
+    let config = parse_settings(config_path)?;
+    let actuals = task_actuals(&HistoryLog::new(&config.history_path).load()?);
+    let (open, done) = readin_all_tasks(&config.task_path)?;
+    let actuals_of = |assign: &Assignment| actuals.get(&(assign.tag.clone(), assign.name.clone()));
+    let actual_of = |assign: &Assignment| actuals_of(assign).map_or(0, |actuals| actuals.open);
+
+    let mut by_tag: HashMap<&str, TagAccuracy> = HashMap::new();
+    for done_task in &done {
+        let assign = &done_task.task;
+        if let Some(estimate) = assign.estimate {
+            let actual =
+                actuals_of(assign).map_or(0, |actuals| actuals.done_at(done_task.completed));
+            let accuracy = by_tag.entry(&assign.tag).or_default();
+            accuracy.tasks += 1;
+            accuracy.estimated += estimate as i64;
+            accuracy.actual += actual;
+            accuracy.within += (actual <= estimate as i64) as usize;
+        }
+    }
+
+    println!("estimates of done tasks");
+    println!(
+        "{:<20} {:>6} {:>10} {:>8} {:>8} {:>16}",
+        "tag", "tasks", "estimated", "actual", "ratio", "within estimate"
+    );
+    // tags of the config first, then any that were dropped since
+    let mut tags: Vec<&str> = config.tags.iter().map(|tag| tag.as_str()).collect();
+    let mut dropped: Vec<&str> = by_tag
+        .keys()
+        .filter(|tag| !tags.contains(tag))
+        .copied()
+        .collect();
+    dropped.sort_unstable();
+    tags.extend(dropped);
+    for tag in tags {
+        match by_tag.get(tag) {
+            Some(accuracy) => println!(
+                "{:<20} {:>6} {:>10} {:>8} {:>8.2} {:>15.1}%",
+                tag,
+                accuracy.tasks,
+                accuracy.estimated,
+                accuracy.actual,
+                accuracy.actual as f64 / accuracy.estimated as f64,
+                accuracy.within as f64 / accuracy.tasks as f64 * 100.0
+            ),
+            None => println!(
+                "{:<20} {:>6} {:>10} {:>8} {:>8} {:>16}",
+                tag, 0, 0, 0, "-", "-"
+            ),
+        }
+    }
+
+    let over: Vec<(&Assignment, i64)> = open
+        .iter()
+        .filter_map(|assign| {
+            let estimate = assign.estimate? as i64;
+            let actual = actual_of(assign);
+            if actual > estimate {
+                Some((assign, actual))
+            } else {
+                None
+            }
+        })
+        .collect();
+    println!();
+    if over.is_empty() {
+        println!("no open task is over its estimate");
+    } else {
+        println!("open tasks over their estimate");
+        println!("{:<32} {:<12} {:>10}", "task", "tag", "pomodoros");
+        for (assign, actual) in over {
+            println!(
+                "{:<32} {:<12} {:>10}",
+                assign.name,
+                assign.tag,
+                assign.progress(actual)
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use super::errors::{collect_problems, RkmError};
+use super::settings_util::WEEKDAYS;
+use chrono::prelude::*;
+use config::Value;
+
+// THESE ARE ALL FUNCTIONS RELATED TO TAG WEIGHTS
+// THAT CHANGE WITH THE TIME OF DAY
+//
+
+// One [[schedule]] block of the config: the tag weights
+// to use between two times on the given days
+#[derive(Clone)]
+pub struct ScheduleBlock {
+    // counted from monday
+    pub days: Vec<usize>,
+    pub from: NaiveTime,
+    pub to: NaiveTime,
+    pub weights: Vec<f64>,
+}
+
+impl ScheduleBlock {
+    // Is the block in effect at the given time? A block ending
+    // earlier in the day than it starts runs past midnight and
+    // belongs to the day it starts on.
+    pub fn covers(&self, now: DateTime<Local>) -> bool {
+        let day = now.weekday().num_days_from_monday() as usize;
+        let time = now.time();
+        if self.from < self.to {
+            self.days.contains(&day) && self.from <= time && time < self.to
+        } else {
+            let day_before = (day + WEEKDAYS.len() - 1) % WEEKDAYS.len();
+            (self.days.contains(&day) && time >= self.from)
+                || (self.days.contains(&day_before) && time < self.to)
+        }
+    }
+
+    // e.g. "schedule mon, tue 09:00-12:00"
+    pub fn describe(&self) -> String {
+        let days = if self.days.len() == WEEKDAYS.len() {
+            String::from("every day")
+        } else {
+            self.days
+                .iter()
+                .map(|day| WEEKDAYS[*day])
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        format!(
+            "schedule {} {}-{}",
+            days,
+            self.from.format("%H:%M"),
+            self.to.format("%H:%M")
+        )
+    }
+}
+
+// the first block in effect at the given time, if any
+pub fn active_block(schedule: &[ScheduleBlock], now: DateTime<Local>) -> Option<&ScheduleBlock> {
+    schedule.iter().find(|block| block.covers(now))
+}
+
+// Read the [[schedule]] blocks of the config, with a problem for
+// every block that can not be read. Whether the weights fit the
+// tags is checked with the rest of the config.
+pub fn parse_schedule(blocks: Vec<Value>) -> Result<Vec<ScheduleBlock>, Vec<RkmError>> {
+    collect_problems(
+        blocks
+            .into_iter()
+            .enumerate()
+            .map(|(block_idx, block)| parse_block(block_idx, block)),
+    )
+}
+
+// one [[schedule]] block, counted from 0
+fn parse_block(block_idx: usize, block: Value) -> Result<ScheduleBlock, RkmError> {
+    let bad_schedule = |reason: String| RkmError::BadSchedule {
+        block: block_idx + 1,
+        reason,
+    };
+    let mut params = block.into_table().map_err(|_| {
+        bad_schedule(String::from(
+            "expected a table like { from = \"09:00\", to = \"12:00\", weights = [...] }",
+        ))
+    })?;
+
+    let mut time = |key: &str| -> Result<NaiveTime, RkmError> {
+        let value = params
+            .remove(key)
+            .ok_or_else(|| bad_schedule(format!("{} is missing", key)))?
+            .into_string()
+            .map_err(|_| bad_schedule(format!("{} is not a string", key)))?;
+        NaiveTime::parse_from_str(&value, "%H:%M")
+            .map_err(|_| bad_schedule(format!("{} '{}' is not a time like 09:00", key, value)))
+    };
+    let from = time("from")?;
+    let to = time("to")?;
+    if from == to {
+        return Err(bad_schedule(String::from(
+            "from and to can not be the same",
+        )));
+    }
+
+    // every day unless the block says otherwise
+    let days = match params.remove("days") {
+        None => (0..WEEKDAYS.len()).collect(),
+        Some(days) => days
+            .into_array()
+            .map_err(|_| bad_schedule(String::from("days is not a list")))?
+            .into_iter()
+            .map(|day| {
+                let day = day.into_string().unwrap_or_default();
+                WEEKDAYS
+                    .iter()
+                    .position(|weekday| *weekday == day)
+                    .ok_or_else(|| {
+                        bad_schedule(format!("'{}' is not one of {}", day, WEEKDAYS.join(", ")))
+                    })
+            })
+            .collect::<Result<Vec<usize>, _>>()?,
+    };
+
+    let weights = params
+        .remove("weights")
+        .ok_or_else(|| bad_schedule(String::from("weights is missing")))?
+        .into_array()
+        .map_err(|_| bad_schedule(String::from("weights is not a list")))?
+        .into_iter()
+        .map(|weight| weight.into_float())
+        .collect::<Result<Vec<f64>, _>>()
+        .map_err(|_| bad_schedule(String::from("weights are not all numbers")))?;
+
+    Ok(ScheduleBlock {
+        days,
+        from,
+        to,
+        weights,
+    })
+}
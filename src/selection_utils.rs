@@ -0,0 +1,733 @@
+use super::assignment_utils::{
+    readin_tasks, turn_assignmentvector_into_pdf, update_tagweights, Assignment,
+};
+use super::errors::RkmError;
+use super::fileops_utils::append_json_line;
+use super::history_utils::PomodoroRecord;
+use super::rand_utils::{normalize, Dice, DieRoll};
+use super::recency_utils::{drop_recent_tasks, RecencyBoost};
+use super::settings_util::ConfigOptions;
+use super::urgency_utils::UrgencyModel;
+use chrono::prelude::*;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// THESE ARE ALL FUNCTIONS RELATED TO PICKING
+// THE NEXT TASK
+//
+
+// the names strategies go by in the config, in the
+// order 'm' cycles through them
+pub const STRATEGY_NAMES: [&str; 5] = [
+    "weighted",
+    "deadline",
+    "round-robin",
+    "least-recent",
+    "priority",
+];
+
+// Everything a strategy may look at besides the tasks
+pub struct SelectionContext<'a> {
+    // the time the pick is made at
+    pub now: DateTime<Local>,
+    pub tags: &'a [String],
+    // tag weights of today with empty tags taken out
+    pub tag_weights: &'a [f64],
+    pub use_due_dates: &'a [bool],
+    pub urgency: &'a [UrgencyModel],
+    pub recency: &'a RecencyBoost,
+    // how much more likely each level of priority makes a task, per tag
+    pub priority_boosts: &'a [f64],
+    // how likely a task without a due date is next to dated ones
+    pub undated_weight: f64,
+    pub history: &'a [PomodoroRecord],
+}
+
+// Everything a roll is made from: every task in the task file,
+// the tasks that can be picked right now and their weights
+pub struct TaskPool {
+    pub now: DateTime<Local>,
+    pub all_tasks: HashMap<String, Vec<Assignment>>,
+    pub candidates: HashMap<String, Vec<Assignment>>,
+    // the tag weights after each step, kept to explain picks
+    pub configured_tag_weights: Vec<f64>,
+    // the [[schedule]] block or [weights] day they came from
+    pub weights_source: String,
+    pub redistributed_tag_weights: Vec<f64>,
+    pub balanced_tag_weights: Vec<f64>,
+    pub tag_weights: Vec<f64>,
+    pub recency: RecencyBoost,
+    pub priority_boosts: Vec<f64>,
+}
+
+// read in tasks from the task file and weigh them with the
+// tags, tag weights and due date settings in the config, with
+// how far each tag is from its quota for today and with how
+// recently each tag and task was worked on, all as of `now`
+pub fn build_task_pool(
+    config: &ConfigOptions,
+    history: &[PomodoroRecord],
+    tag_ctr: &HashMap<String, i64>,
+    now: DateTime<Local>,
+) -> Result<TaskPool, RkmError> {
+    let all_tasks = readin_tasks(&config.task_path, &config.tags, now)?;
+
+    // tasks served in the last few pomodoros sit this roll out
+    let mut candidates = all_tasks.clone();
+    drop_recent_tasks(&mut candidates, history, config.repeat_window);
+
+    // the weights of the time block or day `now` falls in
+    let (configured_tag_weights, weights_source) = config.tag_weights_at(now)?;
+    let configured_tag_weights = configured_tag_weights.to_vec();
+    let configured_relative_tag_weights =
+        update_tagweights(&candidates, &configured_tag_weights, &config.tags);
+    let recency = RecencyBoost::new(
+        history,
+        config.recency_boost,
+        config.recency_boost_hours,
+        now,
+    );
+    let balanced_tag_weights = config.quota_mode.balance_tag_weights(
+        &configured_relative_tag_weights,
+        &config.tags,
+        tag_ctr,
+    );
+    let tag_weights = recency.boost_tag_weights(&balanced_tag_weights, &config.tags);
+
+    Ok(TaskPool {
+        now,
+        all_tasks,
+        candidates,
+        configured_tag_weights,
+        weights_source,
+        redistributed_tag_weights: configured_relative_tag_weights,
+        balanced_tag_weights,
+        tag_weights,
+        recency,
+        priority_boosts: (0..config.tags.len())
+            .map(|tag_idx| config.priority_boost(tag_idx))
+            .collect(),
+    })
+}
+
+impl TaskPool {
+    // what a strategy gets to pick from this pool with
+    pub fn context<'a>(
+        &'a self,
+        config: &'a ConfigOptions,
+        history: &'a [PomodoroRecord],
+    ) -> SelectionContext<'a> {
+        SelectionContext {
+            now: self.now,
+            tags: &config.tags,
+            tag_weights: &self.tag_weights,
+            use_due_dates: &config.use_due_dates,
+            urgency: &config.urgency,
+            recency: &self.recency,
+            priority_boosts: &self.priority_boosts,
+            undated_weight: config.undated_weight,
+            history,
+        }
+    }
+}
+
+// A way of picking the next task out of the task file.
+// None if there is nothing to pick from.
+pub trait SelectionStrategy {
+    fn name(&self) -> &'static str;
+
+    fn choose(
+        &mut self,
+        tasks: &HashMap<String, Vec<Assignment>>,
+        ctx: &SelectionContext,
+        dice: &mut Dice,
+    ) -> Result<Option<Assignment>, RkmError>;
+}
+
+// Build the strategy going by the given name
+pub fn strategy_from_name(name: &str) -> Result<Box<dyn SelectionStrategy>, RkmError> {
+    match name {
+        "weighted" => Ok(Box::new(Weighted)),
+        "deadline" => Ok(Box::new(EarliestDeadline)),
+        "round-robin" => Ok(Box::new(RoundRobin { last_tag: None })),
+        "least-recent" => Ok(Box::new(LeastRecent)),
+        "priority" => Ok(Box::new(StrictPriority)),
+        _ => Err(RkmError::UnknownStrategy(name.to_string())),
+    }
+}
+
+// One line of the roll log: everything that went into a pick
+#[derive(Serialize)]
+pub struct RollRecord {
+    pub time: DateTime<Local>,
+    pub strategy: String,
+    pub seed: Option<u64>,
+    pub rolls: Vec<DieRoll>,
+    pub tag: String,
+    pub task: String,
+}
+
+// Append only JSONL store of every pick
+pub struct RollLog {
+    path: PathBuf,
+}
+
+impl RollLog {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+        }
+    }
+
+    pub fn append(&self, record: &RollRecord) -> Result<(), RkmError> {
+        append_json_line(&self.path, record)
+    }
+}
+
+// Picks tasks with the current strategy and the session's dice,
+// writing every pick to the roll log if there is one
+pub struct Picker {
+    strategy: Box<dyn SelectionStrategy>,
+    dice: Dice,
+    log: Option<RollLog>,
+    last: Option<RollRecord>,
+}
+
+impl Picker {
+    pub fn new(strategy: Box<dyn SelectionStrategy>, dice: Dice, log: Option<RollLog>) -> Self {
+        Self {
+            strategy,
+            dice,
+            log,
+            last: None,
+        }
+    }
+
+    pub fn strategy_name(&self) -> &'static str {
+        self.strategy.name()
+    }
+
+    pub fn set_strategy(&mut self, strategy: Box<dyn SelectionStrategy>) {
+        self.strategy = strategy;
+    }
+
+    pub fn set_log(&mut self, log: Option<RollLog>) {
+        self.log = log;
+    }
+
+    pub fn pick(
+        &mut self,
+        tasks: &HashMap<String, Vec<Assignment>>,
+        ctx: &SelectionContext,
+    ) -> Result<Option<Assignment>, RkmError> {
+        let chosen = self.strategy.choose(tasks, ctx, &mut self.dice)?;
+        let rolls = self.dice.take_rolls();
+        if let Some(assign) = &chosen {
+            self.record(self.strategy.name(), rolls, assign)?;
+        }
+        Ok(chosen)
+    }
+
+    // a task picked from the task table. nothing is rolled
+    // but it still goes in the roll log
+    pub fn pick_by_hand(&mut self, assign: &Assignment) -> Result<(), RkmError> {
+        self.record("manual", vec![], assign)
+    }
+
+    fn record(
+        &mut self,
+        strategy: &str,
+        rolls: Vec<DieRoll>,
+        assign: &Assignment,
+    ) -> Result<(), RkmError> {
+        let record = RollRecord {
+            time: Local::now(),
+            strategy: strategy.to_string(),
+            seed: self.dice.seed(),
+            rolls,
+            tag: assign.tag.clone(),
+            task: assign.name.clone(),
+        };
+        if let Some(log) = &self.log {
+            log.append(&record)?;
+        }
+        self.last = Some(record);
+        Ok(())
+    }
+
+    // the last pick, if it was of the given task
+    pub fn last_pick_of(&self, assign: &Assignment) -> Option<&RollRecord> {
+        self.last
+            .as_ref()
+            .filter(|record| record.tag == assign.tag && record.task == assign.name)
+    }
+}
+
+// the strategy 'm' switches to after the given one
+pub fn next_strategy(current: &str) -> Box<dyn SelectionStrategy> {
+    let idx = STRATEGY_NAMES
+        .iter()
+        .position(|name| *name == current)
+        .map_or(0, |idx| (idx + 1) % STRATEGY_NAMES.len());
+    strategy_from_name(STRATEGY_NAMES[idx]).unwrap()
+}
+
+// a due date to sort by. tasks without one come after all others
+fn due_order(assign: &Assignment) -> Result<(bool, Option<DateTime<Local>>), RkmError> {
+    let due_date = assign.convert_due_date()?;
+    Ok((due_date.is_none(), due_date))
+}
+
+// the task of a tag that is due first
+fn earliest_due(assignvector: &[Assignment]) -> Result<Option<&Assignment>, RkmError> {
+    let mut earliest = None;
+    let mut earliest_key = None;
+    for assign in assignvector {
+        let key = due_order(assign)?;
+        if earliest_key.map_or(true, |best| key < best) {
+            earliest_key = Some(key);
+            earliest = Some(assign);
+        }
+    }
+    Ok(earliest)
+}
+
+// The tasks of each tag in config order, skipping empty tags. Tags
+// without weight right now (e.g. their quota for today is met) are
+// skipped too, unless none of the tags with tasks has any weight
+fn tags_with_tasks<'a>(
+    tasks: &'a HashMap<String, Vec<Assignment>>,
+    ctx: &SelectionContext,
+) -> Vec<(usize, &'a Vec<Assignment>)> {
+    let with_tasks: Vec<(usize, &Vec<Assignment>)> = ctx
+        .tags
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, tag)| tasks.get(tag).map(|assignvector| (idx, assignvector)))
+        .filter(|(_, assignvector)| !assignvector.is_empty())
+        .collect();
+    let weighted: Vec<(usize, &Vec<Assignment>)> = with_tasks
+        .iter()
+        .filter(|(idx, _)| {
+            ctx.tag_weights
+                .get(*idx)
+                .map_or(true, |weight| *weight > 0.0)
+        })
+        .copied()
+        .collect();
+    if weighted.is_empty() {
+        with_tasks
+    } else {
+        weighted
+    }
+}
+
+// Roll a tag by its weight, then roll a task of that
+// tag weighted by how soon it is due. The default.
+pub struct Weighted;
+
+impl SelectionStrategy for Weighted {
+    fn name(&self) -> &'static str {
+        "weighted"
+    }
+
+    fn choose(
+        &mut self,
+        tasks: &HashMap<String, Vec<Assignment>>,
+        ctx: &SelectionContext,
+        dice: &mut Dice,
+    ) -> Result<Option<Assignment>, RkmError> {
+        // first pick a tag to get an assignment from. tags without
+        // tasks are never rolled, and if none of the tags left have
+        // any weight they are all equally likely
+        let has_tasks: Vec<bool> = ctx
+            .tags
+            .iter()
+            .map(|tag| tasks.get(tag).map_or(false, |v| !v.is_empty()))
+            .collect();
+        if !has_tasks.contains(&true) {
+            return Ok(None);
+        }
+        let mut weights: Vec<f64> = ctx
+            .tag_weights
+            .iter()
+            .zip(&has_tasks)
+            .map(|(weight, has)| if *has { *weight } else { 0.0 })
+            .collect();
+        if weights.iter().sum::<f64>() <= 0.0 {
+            weights = has_tasks.iter().map(|has| *has as u8 as f64).collect();
+        }
+        let tag_roll = dice.roll_die("tag", ctx.tags.to_vec(), &normalize(weights))?;
+        let chosen_tag = &ctx.tags[tag_roll];
+
+        // then get the vector of assignments assigned to that tag
+        let assignvector = tasks.get(chosen_tag).unwrap();
+        // turn this into a pdf and roll an assignment
+        let assignvector_pdf = turn_assignmentvector_into_pdf(
+            assignvector,
+            ctx.use_due_dates[tag_roll],
+            &ctx.urgency[tag_roll],
+            ctx.recency,
+            ctx.now,
+            ctx.priority_boosts[tag_roll],
+            ctx.undated_weight,
+        )?;
+        let names = assignvector
+            .iter()
+            .map(|assign| assign.name.clone())
+            .collect();
+        let task_roll = dice.roll_die("task", names, &assignvector_pdf)?;
+        Ok(Some(assignvector[task_roll].clone()))
+    }
+}
+
+// Always the task that is due first, whatever its tag
+pub struct EarliestDeadline;
+
+impl SelectionStrategy for EarliestDeadline {
+    fn name(&self) -> &'static str {
+        "deadline"
+    }
+
+    fn choose(
+        &mut self,
+        tasks: &HashMap<String, Vec<Assignment>>,
+        ctx: &SelectionContext,
+        _dice: &mut Dice,
+    ) -> Result<Option<Assignment>, RkmError> {
+        let all_tasks: Vec<Assignment> = tags_with_tasks(tasks, ctx)
+            .into_iter()
+            .flat_map(|(_, assignvector)| assignvector.iter().cloned())
+            .collect();
+        Ok(earliest_due(&all_tasks)?.cloned())
+    }
+}
+
+// Take the tags in turn, doing the task of each
+// tag that is due first
+pub struct RoundRobin {
+    last_tag: Option<String>,
+}
+
+impl SelectionStrategy for RoundRobin {
+    fn name(&self) -> &'static str {
+        "round-robin"
+    }
+
+    fn choose(
+        &mut self,
+        tasks: &HashMap<String, Vec<Assignment>>,
+        ctx: &SelectionContext,
+        _dice: &mut Dice,
+    ) -> Result<Option<Assignment>, RkmError> {
+        let last_idx = self
+            .last_tag
+            .as_ref()
+            .and_then(|last_tag| ctx.tags.iter().position(|tag| tag == last_tag));
+        let candidates = tags_with_tasks(tasks, ctx);
+        // the first tag with tasks after the last one done,
+        // wrapping around to the start of the list
+        let next = candidates
+            .iter()
+            .find(|(idx, _)| last_idx.map_or(true, |last_idx| *idx > last_idx))
+            .or_else(|| candidates.first());
+
+        match next {
+            Some((idx, assignvector)) => {
+                self.last_tag = Some(ctx.tags[*idx].clone());
+                Ok(earliest_due(assignvector)?.cloned())
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+// The task that was last worked on the longest time ago.
+// Tasks never worked on come first, then the one due first.
+pub struct LeastRecent;
+
+impl SelectionStrategy for LeastRecent {
+    fn name(&self) -> &'static str {
+        "least-recent"
+    }
+
+    fn choose(
+        &mut self,
+        tasks: &HashMap<String, Vec<Assignment>>,
+        ctx: &SelectionContext,
+        _dice: &mut Dice,
+    ) -> Result<Option<Assignment>, RkmError> {
+        let mut chosen = None;
+        let mut chosen_key = None;
+        for (_, assignvector) in tags_with_tasks(tasks, ctx) {
+            for assign in assignvector {
+                let last_worked = ctx
+                    .history
+                    .iter()
+                    .filter(|record| record.tag == assign.tag && record.task == assign.name)
+                    .map(|record| record.end)
+                    .max();
+                // None sorts before any time so unworked tasks win
+                let key = (last_worked, due_order(assign)?);
+                if chosen_key.map_or(true, |best| key < best) {
+                    chosen_key = Some(key);
+                    chosen = Some(assign);
+                }
+            }
+        }
+        Ok(chosen.cloned())
+    }
+}
+
+// The task with the highest priority (!!! before !! before !
+// before none), whatever its tag. Among those the task that is
+// due first, and among tasks due at the same time the one of
+// the tag listed first in the config.
+pub struct StrictPriority;
+
+impl SelectionStrategy for StrictPriority {
+    fn name(&self) -> &'static str {
+        "priority"
+    }
+
+    fn choose(
+        &mut self,
+        tasks: &HashMap<String, Vec<Assignment>>,
+        ctx: &SelectionContext,
+        _dice: &mut Dice,
+    ) -> Result<Option<Assignment>, RkmError> {
+        let mut chosen = None;
+        let mut chosen_key = None;
+        for (tag_idx, assignvector) in tags_with_tasks(tasks, ctx) {
+            for assign in assignvector {
+                let key = (
+                    Reverse(assign.priority_level()),
+                    due_order(assign)?,
+                    tag_idx,
+                );
+                if chosen_key.map_or(true, |best| key < best) {
+                    chosen_key = Some(key);
+                    chosen = Some(assign);
+                }
+            }
+        }
+        Ok(chosen.cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history_utils::Outcome;
+    use chrono::Duration;
+    use std::str::FromStr;
+
+    // what a strategy picks from: three tags and their tasks
+    // as of a fixed time
+    struct Fixture {
+        now: DateTime<Local>,
+        tags: Vec<String>,
+        tag_weights: Vec<f64>,
+        use_due_dates: Vec<bool>,
+        urgency: Vec<UrgencyModel>,
+        recency: RecencyBoost,
+        priority_boosts: Vec<f64>,
+        history: Vec<PomodoroRecord>,
+        tasks: HashMap<String, Vec<Assignment>>,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let now = Local.ymd(2030, 3, 4).and_hms(9, 0, 0);
+            let due = |hours: i64| (now + Duration::hours(hours)).format("%Y-%m-%d %H:%M");
+            let lines = vec![
+                format!("school, Essay, {}", due(48)),
+                format!("school, Problem set, {}, !", due(5)),
+                format!("job, Report, {}, !!", due(24)),
+                String::from("home, Juggle"),
+            ];
+            let mut tasks: HashMap<String, Vec<Assignment>> = HashMap::new();
+            for line in lines {
+                let assign = Assignment::from_str(&line).unwrap();
+                tasks.entry(assign.tag.clone()).or_default().push(assign);
+            }
+            Self {
+                now,
+                tags: vec!["school".into(), "job".into(), "home".into()],
+                tag_weights: vec![0.5, 0.3, 0.2],
+                use_due_dates: vec![true; 3],
+                urgency: vec![UrgencyModel::default(); 3],
+                recency: RecencyBoost::new(&[], 1.0, 24.0, now),
+                priority_boosts: vec![2.0; 3],
+                history: vec![],
+                tasks,
+            }
+        }
+
+        fn ctx(&self) -> SelectionContext<'_> {
+            SelectionContext {
+                now: self.now,
+                tags: &self.tags,
+                tag_weights: &self.tag_weights,
+                use_due_dates: &self.use_due_dates,
+                urgency: &self.urgency,
+                recency: &self.recency,
+                priority_boosts: &self.priority_boosts,
+                undated_weight: 1.0,
+                history: &self.history,
+            }
+        }
+
+        // the names of the next picks of the strategy going by the given name
+        fn picks(&self, name: &str, seed: u64, count: usize) -> Vec<String> {
+            let mut strategy = strategy_from_name(name).unwrap();
+            let mut dice = Dice::new(Some(seed));
+            (0..count)
+                .map(|_| {
+                    let chosen = strategy.choose(&self.tasks, &self.ctx(), &mut dice);
+                    chosen.unwrap().unwrap().name
+                })
+                .collect()
+        }
+
+        // a pomodoro on the task that ended the given hours ago
+        fn worked_on(&mut self, tag: &str, task: &str, hours_ago: i64) {
+            let end = self.now - Duration::hours(hours_ago);
+            self.history.push(PomodoroRecord {
+                start: end - Duration::minutes(25),
+                end,
+                tag: tag.to_string(),
+                task: task.to_string(),
+                outcome: Outcome::Finished,
+                paused_secs: 0,
+                manual: false,
+            });
+        }
+    }
+
+    #[test]
+    fn weighted_picks_are_the_same_for_the_same_seed() {
+        let fixture = Fixture::new();
+        let picks = fixture.picks("weighted", 42, 30);
+        assert_eq!(picks, fixture.picks("weighted", 42, 30));
+        // every tag with weight comes up in that many rolls
+        for name in ["Report", "Juggle"] {
+            assert!(picks.iter().any(|pick| pick == name));
+        }
+        assert!(picks
+            .iter()
+            .any(|pick| pick == "Essay" || pick == "Problem set"));
+    }
+
+    #[test]
+    fn weighted_rolls_a_tag_then_a_task() {
+        let mut fixture = Fixture::new();
+        fixture.tag_weights = vec![0.0, 1.0, 0.0];
+        let mut dice = Dice::new(Some(7));
+        let chosen = Weighted
+            .choose(&fixture.tasks, &fixture.ctx(), &mut dice)
+            .unwrap()
+            .unwrap();
+        assert_eq!(chosen.name, "Report");
+
+        let rolls = dice.take_rolls();
+        assert_eq!(rolls.len(), 2);
+        assert_eq!(rolls[0].label, "tag");
+        assert_eq!(rolls[0].index, 1);
+        assert_eq!(rolls[1].label, "task");
+        assert_eq!(rolls[1].options, vec!["Report".to_string()]);
+    }
+
+    #[test]
+    fn weighted_never_rolls_a_tag_without_tasks() {
+        let mut fixture = Fixture::new();
+        // school still has its weight but its tasks sit this roll out
+        fixture.tasks.remove("school");
+        let picks = fixture.picks("weighted", 5, 30);
+        assert!(picks
+            .iter()
+            .all(|pick| pick == "Report" || pick == "Juggle"));
+
+        // the tags left are equally likely if none has weight
+        fixture.tag_weights = vec![1.0, 0.0, 0.0];
+        let mut dice = Dice::new(Some(5));
+        Weighted
+            .choose(&fixture.tasks, &fixture.ctx(), &mut dice)
+            .unwrap();
+        assert_eq!(dice.take_rolls()[0].pdf, vec![0.0, 0.5, 0.5]);
+    }
+
+    #[test]
+    fn deadline_picks_the_task_due_first() {
+        let fixture = Fixture::new();
+        assert_eq!(fixture.picks("deadline", 1, 3), vec!["Problem set"; 3]);
+    }
+
+    #[test]
+    fn round_robin_takes_the_tags_in_turn() {
+        let fixture = Fixture::new();
+        assert_eq!(
+            fixture.picks("round-robin", 1, 4),
+            vec!["Problem set", "Report", "Juggle", "Problem set"]
+        );
+    }
+
+    #[test]
+    fn least_recent_picks_tasks_never_worked_on_first() {
+        let mut fixture = Fixture::new();
+        assert_eq!(fixture.picks("least-recent", 1, 1), vec!["Problem set"]);
+
+        fixture.worked_on("school", "Problem set", 1);
+        fixture.worked_on("job", "Report", 3);
+        fixture.worked_on("home", "Juggle", 2);
+        assert_eq!(fixture.picks("least-recent", 1, 1), vec!["Essay"]);
+
+        fixture.worked_on("school", "Essay", 1);
+        assert_eq!(fixture.picks("least-recent", 1, 1), vec!["Report"]);
+    }
+
+    #[test]
+    fn priority_picks_the_highest_priority_first() {
+        let mut fixture = Fixture::new();
+        assert_eq!(fixture.picks("priority", 1, 1), vec!["Report"]);
+
+        // then the task due first
+        fixture.tasks.remove("job");
+        assert_eq!(fixture.picks("priority", 1, 1), vec!["Problem set"]);
+    }
+
+    #[test]
+    fn tags_with_a_met_quota_are_skipped_by_every_strategy() {
+        let mut fixture = Fixture::new();
+        // school and home are done for today
+        fixture.tag_weights = vec![0.0, 1.0, 0.0];
+        for name in STRATEGY_NAMES {
+            assert_eq!(fixture.picks(name, 1, 2), vec!["Report"; 2], "{}", name);
+        }
+        // unless no tag with tasks is left
+        fixture.tasks.remove("job");
+        assert_eq!(fixture.picks("deadline", 1, 1), vec!["Problem set"]);
+    }
+
+    #[test]
+    fn nothing_is_picked_without_tasks() {
+        let mut fixture = Fixture::new();
+        fixture.tasks.values_mut().for_each(|tasks| tasks.clear());
+        for name in STRATEGY_NAMES {
+            let mut strategy = strategy_from_name(name).unwrap();
+            let chosen = strategy.choose(&fixture.tasks, &fixture.ctx(), &mut Dice::new(Some(1)));
+            assert!(chosen.unwrap().is_none(), "{} picked a task", name);
+        }
+    }
+
+    #[test]
+    fn m_cycles_through_every_strategy() {
+        let mut name = "weighted";
+        for expected in STRATEGY_NAMES.iter().cycle().skip(1).take(5) {
+            name = next_strategy(name).name();
+            assert_eq!(name, *expected);
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use super::assignment_utils::Assignment;
+use super::errors::RkmError;
+use super::fileops_utils::write_lines_to_file;
+use super::history_utils::ActivePomodoro;
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// THESE ARE ALL FUNCTIONS RELATED TO THE PHASES
+// A SESSION MOVES THROUGH
+//
+
+// The phases of a pomodoro session
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak,
+    // paused in the middle of another phase
+    Paused(Box<Phase>),
+}
+
+// Emitted every time the session changes phase. The
+// main loop reacts to these instead of to the phases.
+pub struct Transition {
+    pub from: Phase,
+    pub to: Phase,
+}
+
+// Tracks the current phase and how many short breaks
+// were taken since the last long one
+pub struct Session {
+    phase: Phase,
+    short_breaks_taken: i64,
+    maxno_short_breaks: i64,
+}
+
+impl Session {
+    pub fn new(maxno_short_breaks: i64) -> Self {
+        Self {
+            phase: Phase::Work,
+            short_breaks_taken: 0,
+            maxno_short_breaks,
+        }
+    }
+
+    // pick up a session where a snapshot left it
+    pub fn restore(phase: Phase, short_breaks_taken: i64, maxno_short_breaks: i64) -> Self {
+        Self {
+            phase,
+            short_breaks_taken,
+            maxno_short_breaks,
+        }
+    }
+
+    // the config was reloaded. takes effect at the next break
+    pub fn set_maxno_short_breaks(&mut self, maxno_short_breaks: i64) {
+        self.maxno_short_breaks = maxno_short_breaks;
+    }
+
+    pub fn phase(&self) -> &Phase {
+        &self.phase
+    }
+
+    pub fn short_breaks_taken(&self) -> i64 {
+        self.short_breaks_taken
+    }
+
+    pub fn is_working(&self) -> bool {
+        self.phase == Phase::Work
+    }
+
+    pub fn is_paused(&self) -> bool {
+        matches!(self.phase, Phase::Paused(_))
+    }
+
+    // The timer of the current phase ran out. Work is followed
+    // by a short break, or a long one once enough short breaks
+    // were taken. Any break is followed by work. A paused session
+    // stays where it is.
+    pub fn advance(&mut self) -> Transition {
+        let next = match self.phase {
+            Phase::Work if self.short_breaks_taken >= self.maxno_short_breaks => Phase::LongBreak,
+            Phase::Work => Phase::ShortBreak,
+            Phase::ShortBreak => {
+                self.short_breaks_taken += 1;
+                Phase::Work
+            }
+            Phase::LongBreak => {
+                self.short_breaks_taken = 0;
+                Phase::Work
+            }
+            Phase::Paused(_) => self.phase.clone(),
+        };
+        self.move_to(next)
+    }
+
+    // Pause the current phase, or go back to it if already paused
+    pub fn toggle_pause(&mut self) -> Transition {
+        let next = match &self.phase {
+            Phase::Paused(phase) => (**phase).clone(),
+            phase => Phase::Paused(Box::new(phase.clone())),
+        };
+        self.move_to(next)
+    }
+
+    fn move_to(&mut self, next: Phase) -> Transition {
+        let from = std::mem::replace(&mut self.phase, next.clone());
+        Transition { from, to: next }
+    }
+}
+
+// Everything needed to pick an interrupted session back up.
+// Written to the state file on every phase change and
+// periodically while the session runs.
+#[derive(Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    pub saved: DateTime<Local>,
+    pub phase: Phase,
+    pub elapsed_secs: i64,
+    pub short_breaks_taken: i64,
+    pub task: Assignment,
+    pub pomodoro: Option<ActivePomodoro>,
+    pub tag_ctr: HashMap<String, i64>,
+    // missing in state files written before manual picks existed
+    #[serde(default)]
+    pub locked: Option<TaskLock>,
+}
+
+// A task picked by hand that the next pomodoros stick to
+// instead of rolling
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TaskLock {
+    pub task: Assignment,
+    // pomodoros still to come on the task, not counting
+    // the one being worked on
+    pub pomodoros: u32,
+}
+
+pub fn save_snapshot(path: &Path, snapshot: &SessionSnapshot) -> Result<(), RkmError> {
+    let save = || -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        write_lines_to_file(path, &[serde_json::to_string(snapshot)?])
+    };
+    save().map_err(RkmError::file(path))
+}
+
+// Read the state file. A missing or unreadable state file
+// means there is nothing to resume.
+pub fn load_snapshot(path: &Path) -> Option<SessionSnapshot> {
+    let contents = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+pub fn remove_snapshot(path: &Path) -> Result<(), RkmError> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(RkmError::file(path)(e)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the phases a session goes through when every timer runs out
+    fn phases(session: &mut Session, count: usize) -> Vec<Phase> {
+        (0..count).map(|_| session.advance().to).collect()
+    }
+
+    #[test]
+    fn long_break_follows_the_last_short_break() {
+        let mut session = Session::new(2);
+        let expected = vec![
+            Phase::ShortBreak,
+            Phase::Work,
+            Phase::ShortBreak,
+            Phase::Work,
+            Phase::LongBreak,
+            Phase::Work,
+            // and the count starts over
+            Phase::ShortBreak,
+        ];
+        assert!(phases(&mut session, expected.len()) == expected);
+        assert_eq!(session.short_breaks_taken(), 0);
+    }
+
+    #[test]
+    fn no_short_breaks_means_only_long_ones() {
+        let mut session = Session::new(0);
+        assert!(
+            phases(&mut session, 4)
+                == vec![Phase::LongBreak, Phase::Work, Phase::LongBreak, Phase::Work]
+        );
+    }
+
+    #[test]
+    fn restored_session_keeps_counting_breaks() {
+        let mut session = Session::restore(Phase::ShortBreak, 1, 2);
+        assert!(!session.is_working());
+        assert!(phases(&mut session, 3) == vec![Phase::Work, Phase::LongBreak, Phase::Work]);
+        assert_eq!(session.short_breaks_taken(), 0);
+    }
+
+    #[test]
+    fn restored_pause_stays_until_resumed() {
+        let mut session = Session::restore(Phase::Paused(Box::new(Phase::Work)), 1, 2);
+        assert!(session.is_paused());
+        assert!(session.advance().to == Phase::Paused(Box::new(Phase::Work)));
+
+        let transition = session.toggle_pause();
+        assert!(transition.to == Phase::Work);
+        assert!(session.is_working());
+        assert!(session.advance().to == Phase::ShortBreak);
+    }
+}
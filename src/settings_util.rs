@@ -1,7 +1,16 @@
-use chrono::{Datelike, Local};
-use std::collections::HashMap;
-use std::error::Error;
-use std::path::{Path, PathBuf};
+use super::calendar_utils::{parse_overrides, read_holidays, DateOverride, Holiday};
+use super::errors::RkmError;
+use super::quota_utils::{parse_quotas, QuotaMode};
+use super::schedule_utils::{active_block, parse_schedule, ScheduleBlock};
+use super::selection_utils::strategy_from_name;
+use super::urgency_utils::{parse_urgency, UrgencyModel};
+use chrono::{DateTime, Datelike, Local};
+use std::convert::TryFrom;
+use std::path::PathBuf;
+
+// the keys of the [weights] table, in the order
+// chrono counts days from monday
+pub const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
 
 // Take the settings file and convert it
 // to a series of raw values to be used
@@ -12,14 +21,259 @@ pub struct ConfigOptions {
     pub sound_volume: f64,
     pub tags: Vec<String>,
     pub use_due_dates: Vec<bool>,
-    pub initial_tag_weights: Vec<f64>,
+    pub use_priorities: Vec<bool>,
+    pub priority_boost: f64,
+    pub undated_weight: f64,
+    pub urgency: Vec<UrgencyModel>,
+    pub weekday_weights: Vec<Vec<f64>>,
+    pub schedule: Vec<ScheduleBlock>,
+    pub overrides: Vec<DateOverride>,
+    pub holidays_path: Option<PathBuf>,
+    pub holidays: Vec<Holiday>,
+    pub holiday_weights: Vec<f64>,
     pub min_break_time: i64,
     pub max_break_time: i64,
     pub task_time: i64,
     pub maxno_min_breaks: i64,
+    pub history_path: PathBuf,
+    pub state_path: PathBuf,
+    pub selection_strategy: String,
+    pub repeat_window: usize,
+    pub recency_boost: f64,
+    pub recency_boost_hours: f64,
+    pub quota_mode: QuotaMode,
+    pub seed: Option<u64>,
+    pub roll_log_path: PathBuf,
+}
+
+impl ConfigOptions {
+    // how much more likely each level of priority makes a
+    // task of the given tag. 1 if the tag ignores priorities
+    pub fn priority_boost(&self, tag_idx: usize) -> f64 {
+        match self.use_priorities.get(tag_idx) {
+            Some(true) => self.priority_boost,
+            _ => 1.0,
+        }
+    }
+
+    // The tag weights in effect at the given time and where they
+    // come from. The first match wins: an [[overrides]] block for
+    // the date, a holiday in the calendar, a [[schedule]] block
+    // covering the time and last the [weights] of the day. The
+    // day's weights are checked here since a session running past
+    // midnight moves on to a day that was not checked at startup.
+    pub fn tag_weights_at(&self, now: DateTime<Local>) -> Result<(&[f64], String), RkmError> {
+        let date = now.date().naive_local();
+        if let Some(date_override) = self.overrides.iter().find(|o| o.covers(date)) {
+            return Ok((&date_override.weights, date_override.describe()));
+        }
+        if let Some(holiday) = self.holidays.iter().find(|h| h.covers(date)) {
+            return Ok((&self.holiday_weights, format!("holiday {}", holiday.name)));
+        }
+        if let Some(block) = active_block(&self.schedule, now) {
+            return Ok((&block.weights, block.describe()));
+        }
+        let day = now.weekday().num_days_from_monday() as usize;
+        let key = format!("weights.{}", WEEKDAYS[day]);
+        let weights = &self.weekday_weights[day];
+        check_length(&key, weights.len(), self.tags.len())?;
+        check_weight_sum(&key, weights)?;
+        Ok((weights, key))
+    }
+}
+
+// Where files rkm keeps for itself (history etc.) live
+// unless the config says otherwise
+pub fn default_data_path(filename: &str) -> PathBuf {
+    let mut path = dirs::config_dir().unwrap_or_default();
+    path.push("rusty-krab-manager");
+    path.push(filename);
+    path
+}
+
+// make sure a per-tag array in the config has one value per tag
+fn check_length(key: &str, found: usize, taglen: usize) -> Result<(), RkmError> {
+    if found == taglen {
+        Ok(())
+    } else {
+        Err(RkmError::WrongLength {
+            key: key.to_string(),
+            expected: taglen,
+            found,
+        })
+    }
+}
+
+// make sure a weight array in the config sums to 1
+fn check_weight_sum(key: &str, weights: &[f64]) -> Result<(), RkmError> {
+    let error_margin = f64::EPSILON;
+    let sum: f64 = weights.iter().sum();
+    if (sum - 1.0).abs() <= error_margin {
+        Ok(())
+    } else {
+        Err(RkmError::WeightSum {
+            key: key.to_string(),
+            sum,
+        })
+    }
+}
+
+// Read in the config and make sure it can be run today. Only the
+// weights of the current day have to be valid for that.
+pub fn readin_settings(config_path: &str) -> Result<ConfigOptions, RkmError> {
+    let config = parse_settings(config_path)?;
+    let curr_day = Local::now().weekday().num_days_from_monday() as usize;
+    match config_problems(&config, &[curr_day]).into_iter().next() {
+        Some(problem) => Err(problem),
+        None => Ok(config),
+    }
+}
+
+// Find every problem with the values in the config. Weights
+// are only checked for the given days (counted from monday).
+pub fn config_problems(config: &ConfigOptions, days: &[usize]) -> Vec<RkmError> {
+    let mut problems = vec![];
+    if !config.task_path.exists() {
+        problems.push(RkmError::MissingPath {
+            key: "task_filepath",
+            path: config.task_path.clone(),
+        });
+    }
+    if !config.sound_path.exists() {
+        problems.push(RkmError::MissingPath {
+            key: "sound.file",
+            path: config.sound_path.clone(),
+        });
+    }
+
+    if let Some(holidays_path) = &config.holidays_path {
+        if !holidays_path.exists() {
+            problems.push(RkmError::MissingPath {
+                key: "holidays_filepath",
+                path: holidays_path.clone(),
+            });
+        }
+    }
+
+    problems.extend(strategy_from_name(&config.selection_strategy).err());
+    if config.recency_boost < 1.0 {
+        problems.push(RkmError::BadValue {
+            key: "recency_boost",
+            reason: String::from("has to be 1 (off) or more"),
+        });
+    }
+    if config.priority_boost < 1.0 {
+        problems.push(RkmError::BadValue {
+            key: "priority_boost",
+            reason: String::from("has to be 1 (off) or more"),
+        });
+    }
+    if config.undated_weight <= 0.0 {
+        problems.push(RkmError::BadValue {
+            key: "undated_weight",
+            reason: String::from("has to be above 0"),
+        });
+    }
+    if config.recency_boost_hours <= 0.0 {
+        problems.push(RkmError::BadValue {
+            key: "recency_boost_hours",
+            reason: String::from("has to be above 0"),
+        });
+    }
+
+    let taglen = config.tags.len();
+    problems.extend(check_length("use_due_dates", config.use_due_dates.len(), taglen).err());
+    problems.extend(check_length("use_priorities", config.use_priorities.len(), taglen).err());
+    for day in days {
+        let key = format!("weights.{}", WEEKDAYS[*day]);
+        let weights = &config.weekday_weights[*day];
+        problems.extend(check_length(&key, weights.len(), taglen).err());
+        problems.extend(check_weight_sum(&key, weights).err());
+    }
+    // schedule blocks can come up on any day
+    for (block_idx, block) in config.schedule.iter().enumerate() {
+        let key = format!("schedule block {} weights", block_idx + 1);
+        problems.extend(check_length(&key, block.weights.len(), taglen).err());
+        problems.extend(check_weight_sum(&key, &block.weights).err());
+    }
+    // and so can overrides and holidays
+    for (block_idx, block) in config.overrides.iter().enumerate() {
+        let key = format!("overrides block {} weights", block_idx + 1);
+        problems.extend(check_length(&key, block.weights.len(), taglen).err());
+        problems.extend(check_weight_sum(&key, &block.weights).err());
+    }
+    if config.holidays_path.is_some() {
+        problems
+            .extend(check_length("holiday_weights", config.holiday_weights.len(), taglen).err());
+        problems.extend(check_weight_sum("holiday_weights", &config.holiday_weights).err());
+    }
+    problems
 }
 
-pub fn readin_settings(config_path: &str) -> Result<ConfigOptions, Box<dyn Error>> {
+// Every value of the config that can not be read, so all of them
+// can be shown at once. What could not be read is left at a default
+// so the rest of the config can still be read
+#[derive(Default)]
+struct Problems(Vec<RkmError>);
+
+impl Problems {
+    // the value, or the default once the problem with it is noted
+    fn take<T, E: Into<RkmError>>(&mut self, result: Result<T, E>, default: T) -> T {
+        result.unwrap_or_else(|e| {
+            self.0.push(e.into());
+            default
+        })
+    }
+
+    // the same for a section with a problem for each of its entries
+    fn take_all<T>(&mut self, result: Result<T, Vec<RkmError>>, default: T) -> T {
+        result.unwrap_or_else(|problems| {
+            self.0.extend(problems);
+            default
+        })
+    }
+}
+
+// a list of the config with values of one type, e.g. Value::into_float
+fn get_list<T>(
+    settings: &config::Config,
+    key: &str,
+    into: fn(config::Value) -> Result<T, config::ConfigError>,
+) -> Result<Vec<T>, RkmError> {
+    Ok(settings
+        .get_array(key)?
+        .into_iter()
+        .map(into)
+        .collect::<Result<Vec<T>, _>>()?)
+}
+
+// a value that may be left out of the config. a value of
+// the wrong type is still an error
+fn optional<T, E: Into<RkmError>>(result: Result<T, E>) -> Result<Option<T>, RkmError> {
+    match result.map_err(Into::into) {
+        Ok(value) => Ok(Some(value)),
+        Err(RkmError::Config(config::ConfigError::NotFound(_))) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// a number that may be left out of the config. a value of
+// the wrong type is still an error
+fn optional_float(settings: &config::Config, key: &str, default: f64) -> Result<f64, RkmError> {
+    Ok(optional(settings.get_float(key))?.unwrap_or(default))
+}
+
+// Read in the config file without checking whether
+// the values in it make sense. Stops at the first
+// value that can not be read
+pub fn parse_settings(config_path: &str) -> Result<ConfigOptions, RkmError> {
+    parse_all_settings(config_path).map_err(|mut problems| problems.remove(0))
+}
+
+// Read in the config file without checking whether the values
+// in it make sense, with a problem for every value that can
+// not be read rather than just the first
+pub fn parse_all_settings(config_path: &str) -> Result<ConfigOptions, Vec<RkmError>> {
     // Read in configuration
     //let mut settings = config::Config::new();
     //settings.merge(config::File::with_name(config_path))?;
@@ -31,93 +285,192 @@ pub fn readin_settings(config_path: &str) -> Result<ConfigOptions, Box<dyn Error
         // Eg.. `APP_DEBUG=1 ./target/app` would set the `debug` key
         .add_source(config::Environment::with_prefix("APP"))
         .build()
-        .unwrap();
+        .map_err(|e| vec![e.into()])?;
+    let mut problems = Problems::default();
 
     // get the paths to the task file and sound file
-    let task_path = settings.get("task_filepath")?;
-    assert!(
-        Path::new(&task_path).exists(),
-        "task filepath does not exist"
+    let task_path: PathBuf = problems.take(settings.get("task_filepath"), PathBuf::new());
+    let sound_path: PathBuf = problems.take(settings.get("sound.file"), PathBuf::new());
+    let sound_volume = problems.take(settings.get_float("sound.volume"), 1.0);
+
+    // get the vector of tags. what depends on the tags is
+    // only read if they could be
+    let tags = problems.take(
+        get_list(&settings, "tags", config::Value::into_string).map(Some),
+        None,
     );
+    let taglen = tags.as_ref().map_or(0, |tags| tags.len());
 
-    let sound_path = settings.get("sound.file")?;
-    assert!(
-        Path::new(&sound_path).exists(),
-        "Sound filepath does not exist"
+    // get boolean vector of whether to use due dates or not
+    let use_due_dates = problems.take(
+        get_list(&settings, "use_due_dates", config::Value::into_bool),
+        vec![],
     );
-    let sound_volume = settings.get_float("sound.volume")?;
 
-    // get the vector of tags
-    let tags = settings.get_array("tags")?;
-    //let tags: Vec<String> = tags.into_iter().map(|i| i.into_str().unwrap()).collect();
-    let tags: Vec<String> = tags.into_iter().map(|i| i.into_string().unwrap()).collect();
-    //let tags: Vec<String> = tags.into_iter().map(|i| i.unwrap()).collect();
-    let taglen = tags.len();
+    // whether tasks with a priority are more likely, per tag,
+    // and how much more likely each level makes them. optional
+    let use_priorities = problems
+        .take(
+            optional(get_list(
+                &settings,
+                "use_priorities",
+                config::Value::into_bool,
+            )),
+            None,
+        )
+        .unwrap_or_else(|| vec![false; taglen]);
+    let priority_boost = problems.take(optional_float(&settings, "priority_boost", 2.0), 2.0);
+    let undated_weight = problems.take(optional_float(&settings, "undated_weight", 1.0), 1.0);
 
-    // get boolean vector of whether to use due dates or not
-    let use_due_dates = settings.get_array("use_due_dates")?;
-    let use_due_dates: Vec<bool> = use_due_dates
-        .into_iter()
-        .map(|i| i.into_bool().unwrap())
+    // how tasks get more likely as they come due, per tag. optional
+    let urgency = match (&tags, optional(settings.get_table("urgency"))) {
+        (Some(tags), Ok(Some(table))) => problems.take_all(parse_urgency(table, tags), vec![]),
+        (_, Ok(_)) => vec![UrgencyModel::default(); taglen],
+        (_, Err(e)) => problems.take(Err(e), vec![]),
+    };
+
+    // get weights tags for all days of the week
+    let weekday_weights = WEEKDAYS
+        .iter()
+        .map(|day| {
+            let path = format!("weights.{}", day);
+            problems.take(
+                get_list(&settings, &path, config::Value::into_float),
+                vec![],
+            )
+        })
         .collect();
 
-    assert!(
-        taglen == use_due_dates.len(),
-        "use_due_dates vector length does not match number of tags in config"
-    );
+    // weights for blocks of time that win over the ones of the
+    // day. optional
+    let schedule = match optional(settings.get_array("schedule")) {
+        Ok(Some(blocks)) => problems.take_all(parse_schedule(blocks), vec![]),
+        Ok(None) => vec![],
+        Err(e) => problems.take(Err(e), vec![]),
+    };
 
-    // get weights tags for all days of the week
-    let mut weights_map = HashMap::new();
-    for day in ["mon", "tue", "wed", "thu", "fri", "sat", "sun"] {
-        let path = format!("weights.{}", day);
-        let weights: Vec<f64> = settings
-            .get_array(&path)?
-            .into_iter()
-            .map(|i| i.into_float().unwrap())
-            .collect();
-        weights_map.insert(day, weights);
-    }
-
-    let curr_day: u32 = Local::now().weekday().number_from_monday();
-    let tag_weights = match curr_day {
-        1 => weights_map.remove("mon"),
-        2 => weights_map.remove("tue"),
-        3 => weights_map.remove("wed"),
-        4 => weights_map.remove("thu"),
-        5 => weights_map.remove("fri"),
-        6 => weights_map.remove("sat"),
-        7 => weights_map.remove("sun"),
-        _ => unreachable!(),
-    }
-    .unwrap();
-    assert!(
-        taglen == tag_weights.len(),
-        "current day tag weights do not match number of tags in config"
-    );
+    // weights for particular dates and for holidays in an .ics
+    // calendar, winning over everything else. optional
+    let overrides = match optional(settings.get_array("overrides")) {
+        Ok(Some(blocks)) => problems.take_all(parse_overrides(blocks), vec![]),
+        Ok(None) => vec![],
+        Err(e) => problems.take(Err(e), vec![]),
+    };
+    let holidays_path: Option<PathBuf> =
+        problems.take(optional(settings.get("holidays_filepath")), None);
+    let (holidays, holiday_weights) = match &holidays_path {
+        Some(holidays_path) => {
+            let holiday_weights = problems.take(
+                get_list(&settings, "holiday_weights", config::Value::into_float),
+                vec![],
+            );
+            // a missing calendar is reported with the other paths
+            let holidays = if holidays_path.exists() {
+                problems.take(read_holidays(holidays_path), vec![])
+            } else {
+                vec![]
+            };
+            (holidays, holiday_weights)
+        }
+        None => (vec![], vec![]),
+    };
 
-    let error_margin = f64::EPSILON;
-    let tag_weights_sum: f64 = tag_weights.iter().sum();
-    assert!(
-        (tag_weights_sum - 1.0).abs() <= error_margin,
-        "current day tag weights do not sum to 1. they sum to {}",
-        tag_weights_sum
-    );
+    let min_break_time = problems.take(settings.get_int("short_break_time"), 0);
+    let max_break_time = problems.take(settings.get_int("long_break_time"), 0);
+    let task_time = problems.take(settings.get_int("task_time"), 0);
+    let maxno_min_breaks = problems.take(settings.get_int("maxno_short_breaks"), 0);
+
+    // where to log every pomodoro. optional
+    let history_path = problems
+        .take(optional(settings.get("history_filepath")), None)
+        .unwrap_or_else(|| default_data_path("history.jsonl"));
+
+    // where to snapshot the running session. optional
+    let state_path = problems
+        .take(optional(settings.get("state_filepath")), None)
+        .unwrap_or_else(|| default_data_path("session.json"));
+
+    // how to pick the next task. optional
+    let selection_strategy = problems
+        .take(optional(settings.get::<String>("selection_strategy")), None)
+        .unwrap_or_else(|| String::from("weighted"));
 
-    let min_break_time = settings.get_int("short_break_time")?;
-    let max_break_time = settings.get_int("long_break_time")?;
-    let task_time = settings.get_int("task_time")?;
-    let maxno_min_breaks = settings.get_int("maxno_short_breaks")?;
+    // how many of the last pomodoros a task sits out after being
+    // served, and how much more likely things not worked on in a
+    // while get. optional, off by default
+    let repeat_window = optional(settings.get_int("repeat_window")).and_then(|window| {
+        usize::try_from(window.unwrap_or(0)).map_err(|_| RkmError::BadValue {
+            key: "repeat_window",
+            reason: String::from("can not be negative"),
+        })
+    });
+    let repeat_window = problems.take(repeat_window, 0);
+    let recency_boost = problems.take(optional_float(&settings, "recency_boost", 1.0), 1.0);
+    let recency_boost_hours =
+        problems.take(optional_float(&settings, "recency_boost_hours", 24.0), 24.0);
 
+    // daily targets per tag. optional, off by default
+    let quota_mode = problems
+        .take(optional(settings.get::<String>("quota_mode")), None)
+        .unwrap_or_else(|| String::from("off"));
+    let quota_table = problems.take(optional(settings.get_table("quotas")), None);
+    let quota_mode = match &tags {
+        Some(tags) => problems.take(
+            parse_quotas(&quota_mode, quota_table.unwrap_or_default(), tags),
+            QuotaMode::Off,
+        ),
+        None => QuotaMode::Off,
+    };
+
+    // seed the dice to make rolls reproducible. optional
+    let seed = optional(settings.get_int("seed")).and_then(|seed| {
+        seed.map(|seed| {
+            u64::try_from(seed).map_err(|_| RkmError::BadValue {
+                key: "seed",
+                reason: String::from("can not be negative"),
+            })
+        })
+        .transpose()
+    });
+    let seed = problems.take(seed, None);
+
+    // where every roll is logged. optional
+    let roll_log_path = problems
+        .take(optional(settings.get("roll_log_filepath")), None)
+        .unwrap_or_else(|| default_data_path("rolls.jsonl"));
+
+    if !problems.0.is_empty() {
+        return Err(problems.0);
+    }
+    let tags = tags.unwrap_or_default();
     Ok(ConfigOptions {
         task_path,
         sound_path,
         sound_volume,
         tags,
         use_due_dates,
-        initial_tag_weights: tag_weights,
+        use_priorities,
+        priority_boost,
+        undated_weight,
+        urgency,
+        weekday_weights,
+        schedule,
+        overrides,
+        holidays_path,
+        holidays,
+        holiday_weights,
         min_break_time,
         max_break_time,
         task_time,
         maxno_min_breaks,
+        history_path,
+        state_path,
+        selection_strategy,
+        repeat_window,
+        recency_boost,
+        recency_boost_hours,
+        quota_mode,
+        seed,
+        roll_log_path,
     })
 }
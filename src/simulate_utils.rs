@@ -0,0 +1,196 @@
+use super::assignment_utils::{
+    get_tag_counter_hashmap, hashmap_to_taskvector, readin_tasks, Assignment,
+};
+use super::errors::RkmError;
+use super::history_utils::{HistoryLog, Outcome, PomodoroRecord};
+use super::rand_utils::Dice;
+use super::selection_utils::{build_task_pool, strategy_from_name, Picker};
+use super::session_utils::{Phase, Session};
+use super::settings_util::{config_problems, ConfigOptions, WEEKDAYS};
+use chrono::prelude::*;
+use chrono::Duration;
+
+// THESE ARE ALL FUNCTIONS RELATED TO THE 'simulate'
+// SUBCOMMAND
+//
+
+// What to simulate, from the command line
+pub struct SimulationOptions {
+    // how many days to simulate
+    pub runs: usize,
+    // the day whose weights are used, counted from monday
+    pub weekday: usize,
+    pub pomodoros: usize,
+    // when the first pomodoro of the day starts
+    pub start: NaiveTime,
+    pub seed: Option<u64>,
+}
+
+// the next date falling on the given weekday, today included
+fn next_weekday(weekday: usize) -> Date<Local> {
+    let today = Local::today();
+    let days_ahead = (weekday + 7 - today.weekday().num_days_from_monday() as usize) % 7;
+    today + Duration::days(days_ahead as i64)
+}
+
+// Roll a day of pomodoros the way the TUI would, over and over,
+// and print how often each tag and task came up. The real history
+// is taken as the history before the day. Tasks are never marked
+// as complete, they only drop out once they are overdue.
+pub fn run_simulation(config: &ConfigOptions, options: &SimulationOptions) -> Result<(), RkmError> {
+    if let Some(problem) = config_problems(config, &[options.weekday])
+        .into_iter()
+        .next()
+    {
+        return Err(problem);
+    }
+
+    let day_start = next_weekday(options.weekday)
+        .and_time(options.start)
+        .ok_or_else(|| RkmError::BadValue {
+            key: "--start",
+            reason: String::from("does not exist on that day"),
+        })?;
+    let past = HistoryLog::new(&config.history_path).load()?;
+    let tasks = hashmap_to_taskvector(
+        &readin_tasks(&config.task_path, &config.tags, day_start)?,
+        &config.tags,
+    );
+
+    let mut picker = Picker::new(
+        strategy_from_name(&config.selection_strategy)?,
+        Dice::new(options.seed),
+        None,
+    );
+    let mut tag_totals = vec![0; config.tags.len()];
+    // the configured weights of every roll added up. they change
+    // through the day with a [[schedule]]
+    let mut weight_totals = vec![0.0; config.tags.len()];
+    let mut rolls = 0;
+    let mut task_totals = vec![0; tasks.len()];
+    // runs in which a task got a pomodoro before it was due
+    let mut in_time = vec![0; tasks.len()];
+
+    for _ in 0..options.runs {
+        // strategies like round-robin start each day afresh
+        picker.set_strategy(strategy_from_name(&config.selection_strategy)?);
+        let mut history = past.clone();
+        let mut tag_ctr = get_tag_counter_hashmap(&config.tags);
+        let mut session = Session::new(config.maxno_min_breaks);
+        let mut now = day_start;
+        let mut worked_in_time = vec![false; tasks.len()];
+
+        for _ in 0..options.pomodoros {
+            // everything left is overdue, the day is over
+            let pool = match build_task_pool(config, &history, &tag_ctr, now) {
+                Ok(pool) => pool,
+                Err(RkmError::EmptyTaskList(_)) => break,
+                Err(e) => return Err(e),
+            };
+            let chosen = match picker.pick(&pool.candidates, &pool.context(config, &history))? {
+                Some(chosen) => chosen,
+                None => break,
+            };
+
+            for (total, weight) in weight_totals.iter_mut().zip(&pool.configured_tag_weights) {
+                *total += weight;
+            }
+            rolls += 1;
+
+            let end = now + Duration::minutes(config.task_time);
+            if let Some(tag_idx) = config.tags.iter().position(|tag| *tag == chosen.tag) {
+                tag_totals[tag_idx] += 1;
+            }
+            if let Some(task_idx) = tasks.iter().position(|assign| *assign == chosen) {
+                task_totals[task_idx] += 1;
+                if !chosen.is_overdue(now)? {
+                    worked_in_time[task_idx] = true;
+                }
+            }
+            if let Some(ctr) = tag_ctr.get_mut(&chosen.tag) {
+                *ctr += 1;
+            }
+            history.push(PomodoroRecord {
+                start: now,
+                end,
+                tag: chosen.tag,
+                task: chosen.name,
+                outcome: Outcome::Finished,
+                paused_secs: 0,
+                manual: false,
+            });
+
+            // then the break, and back to work
+            let break_minutes = match session.advance().to {
+                Phase::LongBreak => config.max_break_time,
+                _ => config.min_break_time,
+            };
+            session.advance();
+            now = end + Duration::minutes(break_minutes);
+        }
+
+        for (count, worked) in in_time.iter_mut().zip(worked_in_time) {
+            *count += worked as usize;
+        }
+    }
+
+    print_simulation(
+        options,
+        &config.tags,
+        &weight_totals
+            .iter()
+            .map(|total| total / rolls.max(1) as f64)
+            .collect::<Vec<f64>>(),
+        &tasks,
+        &tag_totals,
+        &task_totals,
+        &in_time,
+    );
+    Ok(())
+}
+
+fn print_simulation(
+    options: &SimulationOptions,
+    tags: &[String],
+    tag_weights: &[f64],
+    tasks: &[Assignment],
+    tag_totals: &[usize],
+    task_totals: &[usize],
+    in_time: &[usize],
+) {
+    let runs = options.runs as f64;
+    println!(
+        "simulated {} {} days of {} pomodoros starting at {}",
+        options.runs,
+        WEEKDAYS[options.weekday],
+        options.pomodoros,
+        options.start.format("%H:%M")
+    );
+
+    println!();
+    println!("{:<20} {:>10} {:>14}", "tag", "avg weight", "pomodoros/day");
+    for ((tag, weight), total) in tags.iter().zip(tag_weights).zip(tag_totals) {
+        println!(
+            "{:<20} {:>9.2}% {:>14.2}",
+            tag,
+            weight * 100.0,
+            *total as f64 / runs
+        );
+    }
+
+    println!();
+    println!(
+        "{:<32} {:<12} {:<16} {:>14} {:>16}",
+        "task", "tag", "due", "pomodoros/day", "done before due"
+    );
+    for ((assign, total), in_time) in tasks.iter().zip(task_totals).zip(in_time) {
+        println!(
+            "{:<32} {:<12} {:<16} {:>14.2} {:>15.1}%",
+            assign.name,
+            assign.tag,
+            assign.due_label(),
+            *total as f64 / runs,
+            *in_time as f64 / runs * 100.0
+        );
+    }
+}
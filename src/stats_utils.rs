@@ -0,0 +1,144 @@
+use super::history_utils::PomodoroRecord;
+use chrono::prelude::*;
+use chrono::Duration;
+use std::collections::BTreeSet;
+
+// THESE ARE ALL FUNCTIONS RELATED TO THE HISTORICAL
+// STATS SCREEN
+//
+
+// Everything the history screen shows, computed once
+// from the history log whenever the screen is opened
+pub struct HistoryStats {
+    pub days: i64,
+    pub tags: Vec<String>,
+    // pomodoros per tag over the window
+    pub tag_totals: Vec<(String, u64)>,
+    // pomodoros per day over the window, oldest first
+    pub daily_totals: Vec<u64>,
+    // pomodoros per tag and weekday. indexed [tag][weekday]
+    pub weekday_counts: Vec<Vec<u64>>,
+    pub current_streak: i64,
+    pub longest_streak: i64,
+    // (configured weight, share of time actually worked)
+    // per weekday and tag. indexed [weekday][tag]
+    pub weight_shares: Vec<Vec<(f64, f64)>>,
+}
+
+// Summarise the history log over the last `days` days
+// (today included) for the tags in the config
+pub fn compute_history_stats(
+    records: &[PomodoroRecord],
+    tag_vector: &[String],
+    weekday_weights: &[Vec<f64>],
+    days: i64,
+) -> HistoryStats {
+    let today = Local::now().naive_local().date();
+    let first_day = today - Duration::days(days - 1);
+    let in_window: Vec<&PomodoroRecord> = records
+        .iter()
+        .filter(|record| {
+            let day = record.start.naive_local().date();
+            day >= first_day && day <= today
+        })
+        .collect();
+
+    let mut tag_totals: Vec<(String, u64)> =
+        tag_vector.iter().map(|tag| (tag.clone(), 0)).collect();
+    let mut daily_totals = vec![0; days as usize];
+    let mut weekday_counts = vec![vec![0; 7]; tag_vector.len()];
+    let mut weekday_seconds = vec![vec![0_i64; tag_vector.len()]; 7];
+
+    for record in &in_window {
+        let day = record.start.naive_local().date();
+        let weekday = day.weekday().num_days_from_monday() as usize;
+        let tag_idx = tag_vector.iter().position(|tag| *tag == record.tag);
+
+        // time counts no matter how the pomodoro ended
+        if let Some(tag_idx) = tag_idx {
+            weekday_seconds[weekday][tag_idx] += record.worked().num_seconds().max(0);
+        }
+
+        if !record.outcome.counts_as_done() {
+            continue;
+        }
+        daily_totals[day.signed_duration_since(first_day).num_days() as usize] += 1;
+        if let Some(tag_idx) = tag_idx {
+            tag_totals[tag_idx].1 += 1;
+            weekday_counts[tag_idx][weekday] += 1;
+        }
+    }
+
+    let weight_shares = weekday_seconds
+        .iter()
+        .enumerate()
+        .map(|(weekday, seconds)| {
+            let total: i64 = seconds.iter().sum();
+            seconds
+                .iter()
+                .enumerate()
+                .map(|(tag_idx, secs)| {
+                    // other days than today may not have been validated
+                    let configured = weekday_weights[weekday]
+                        .get(tag_idx)
+                        .copied()
+                        .unwrap_or_default();
+                    let actual = if total > 0 {
+                        *secs as f64 / total as f64
+                    } else {
+                        0.0
+                    };
+                    (configured, actual)
+                })
+                .collect()
+        })
+        .collect();
+
+    let (current_streak, longest_streak) = find_streaks(records, today);
+
+    HistoryStats {
+        days,
+        tags: tag_vector.to_vec(),
+        tag_totals,
+        daily_totals,
+        weekday_counts,
+        current_streak,
+        longest_streak,
+        weight_shares,
+    }
+}
+
+// Find the current and longest run of consecutive days with at
+// least one pomodoro done. A streak is still current if nothing
+// has been done yet today but yesterday counted.
+fn find_streaks(records: &[PomodoroRecord], today: NaiveDate) -> (i64, i64) {
+    let active_days: BTreeSet<NaiveDate> = records
+        .iter()
+        .filter(|record| record.outcome.counts_as_done())
+        .map(|record| record.start.naive_local().date())
+        .collect();
+
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for day in &active_days {
+        run = match previous {
+            Some(prev) if *day - prev == Duration::days(1) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(*day);
+    }
+
+    let mut day = if active_days.contains(&today) {
+        today
+    } else {
+        today - Duration::days(1)
+    };
+    let mut current = 0;
+    while active_days.contains(&day) {
+        current += 1;
+        day -= Duration::days(1);
+    }
+    (current, longest)
+}
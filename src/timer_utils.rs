@@ -0,0 +1,92 @@
+use chrono::prelude::*;
+use chrono::Duration;
+
+// THESE ARE ALL FUNCTIONS RELATED TO THE TIMER
+//
+
+// A countdown anchored to wall clock timestamps. Progress is
+// computed from the real time elapsed since the start, minus any
+// time spent paused, so it stays correct across a suspend or a
+// late tick. The tick rate only decides how often it is redrawn.
+pub struct Timer {
+    start: DateTime<Local>,
+    length: Duration,
+    paused: Duration,
+    pause_start: Option<DateTime<Local>>,
+}
+
+impl Timer {
+    // start a new countdown of the given number of minutes
+    pub fn start(minutes: i64) -> Self {
+        Self {
+            start: Local::now(),
+            length: Duration::minutes(minutes),
+            paused: Duration::zero(),
+            pause_start: None,
+        }
+    }
+
+    // start a countdown of the given number of minutes that
+    // already ran for some time, e.g. when resuming a session
+    pub fn start_from(minutes: i64, elapsed: Duration) -> Self {
+        Self {
+            start: Local::now() - elapsed,
+            ..Self::start(minutes)
+        }
+    }
+
+    pub fn pause(&mut self) {
+        if self.pause_start.is_none() {
+            self.pause_start = Some(Local::now());
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if let Some(pause_start) = self.pause_start.take() {
+            self.paused = self.paused + Local::now().signed_duration_since(pause_start);
+        }
+    }
+
+    // time counted down so far, not including pauses
+    pub fn elapsed(&self) -> Duration {
+        let now = self.pause_start.unwrap_or_else(Local::now);
+        now.signed_duration_since(self.start) - self.paused
+    }
+
+    pub fn remaining(&self) -> Duration {
+        (self.length - self.elapsed()).max(Duration::zero())
+    }
+
+    // fraction of the countdown done, between 0 and 1
+    pub fn progress(&self) -> f64 {
+        if self.length <= Duration::zero() {
+            return 1.0;
+        }
+        let ratio =
+            self.elapsed().num_milliseconds() as f64 / self.length.num_milliseconds() as f64;
+        ratio.clamp(0.0, 1.0)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.elapsed() >= self.length
+    }
+
+    // jump to the end of the countdown
+    pub fn fast_forward(&mut self) {
+        self.start = self.start - (self.length - self.elapsed());
+    }
+
+    // start the countdown over with the same length
+    pub fn rewind(&mut self) {
+        let paused = self.pause_start.is_some();
+        *self = Self {
+            start: Local::now(),
+            length: self.length,
+            paused: Duration::zero(),
+            pause_start: None,
+        };
+        if paused {
+            self.pause();
+        }
+    }
+}
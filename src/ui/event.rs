@@ -1,4 +1,7 @@
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::fs;
 use std::io;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc;
 use std::thread;
 use std::time::Duration;
@@ -8,15 +11,20 @@ use termion::input::TermRead;
 pub enum Event<I> {
     Input(I),
     Tick,
+    // one of the watched files was changed on disk
+    Changed(PathBuf),
 }
 
-/// A small event handler that wrap termion input and tick events. Each event
-/// type is handled in its own thread and returned to a common `Receiver`
+/// A small event handler that wrap termion input, tick and file change events.
+/// Each event type is handled in its own thread and returned to a common `Receiver`
 #[allow(dead_code)]
 pub struct Events {
     rx: mpsc::Receiver<Event<Key>>,
     input_handle: thread::JoinHandle<()>,
     tick_handle: thread::JoinHandle<()>,
+    tx: mpsc::Sender<Event<Key>>,
+    // None until the first call to watch
+    watcher: Option<RecommendedWatcher>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -34,6 +42,30 @@ impl Default for Config {
     }
 }
 
+// A watched file as the watcher reports it: the canonical directory
+// it is in joined with its name. The directory is watched rather than
+// the file so a file an editor replaces by renaming is still seen
+fn watched_path(path: &Path) -> Option<PathBuf> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    Some(fs::canonicalize(dir).ok()?.join(path.file_name()?))
+}
+
+// every path a change to this file can be reported under. if the file
+// is a symlink, writes to it land on the file it points to, which may
+// be in another directory
+fn watched_paths(path: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = watched_path(path).into_iter().collect();
+    if let Ok(target) = fs::canonicalize(path) {
+        if !paths.contains(&target) {
+            paths.push(target);
+        }
+    }
+    paths
+}
+
 impl Events {
     pub fn new() -> Self {
         Self::with_config(Config::default())
@@ -57,20 +89,58 @@ impl Events {
             })
         };
         let tick_handle = {
-            // let tx = tx.clone();
-            thread::spawn(move || {
-                let tx = tx.clone();
-                loop {
-                    tx.send(Event::Tick).unwrap();
-                    thread::sleep(config.tick_rate);
-                }
+            let tx = tx.clone();
+            thread::spawn(move || loop {
+                tx.send(Event::Tick).unwrap();
+                thread::sleep(config.tick_rate);
             })
         };
         Self {
             rx,
             input_handle,
             tick_handle,
+            tx,
+            watcher: None,
+        }
+    }
+
+    // send a Changed event whenever one of these files changes.
+    // replaces whatever was watched before
+    pub fn watch(&mut self, paths: Vec<PathBuf>) -> notify::Result<()> {
+        // the old watcher stops watching when it is dropped
+        self.watcher = None;
+        let watched: Vec<(Vec<PathBuf>, PathBuf)> = paths
+            .into_iter()
+            .map(|path| (watched_paths(&path), path))
+            .filter(|(targets, _)| !targets.is_empty())
+            .collect();
+
+        let tx = self.tx.clone();
+        let watching = watched.clone();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let event = match event {
+                    // reading a file does not change it
+                    Ok(event) if !matches!(event.kind, EventKind::Access(_)) => event,
+                    _ => return,
+                };
+                for (targets, path) in &watching {
+                    if targets.iter().any(|target| event.paths.contains(target)) {
+                        let _ = tx.send(Event::Changed(path.clone()));
+                    }
+                }
+            })?;
+        let mut dirs: Vec<&Path> = watched
+            .iter()
+            .flat_map(|(targets, _)| targets.iter().filter_map(|target| target.parent()))
+            .collect();
+        dirs.sort();
+        dirs.dedup();
+        for dir in dirs {
+            watcher.watch(dir, RecursiveMode::NonRecursive)?;
         }
+        self.watcher = Some(watcher);
+        Ok(())
     }
 
     pub fn next(&self) -> Result<Event<Key>, mpsc::RecvError> {
@@ -1,11 +1,19 @@
 pub mod event;
+use crate::assignment_utils::{assignment_from_input, Assignment};
+use crate::settings_util::WEEKDAYS;
+use crate::stats_utils::HistoryStats;
+use crate::timer_utils::Timer;
+use crate::urgency_utils::UrgencyModel;
+use chrono::Local;
+use std::collections::HashMap;
+use termion::event::Key;
 use tui::backend::Backend;
-use tui::layout::{Alignment, Constraint, Rect};
+use tui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 use tui::style::{Color, Modifier, Style};
 use tui::text::{Span, Spans};
 use tui::widgets::{
-    Block, BorderType, Borders, Cell, Gauge, List, ListItem, Paragraph, Row, Table, TableState,
-    Wrap,
+    BarChart, Block, BorderType, Borders, Cell, Clear, Gauge, List, ListItem, Paragraph, Row,
+    Sparkline, Table, TableState, Wrap,
 };
 use tui::Frame;
 
@@ -13,13 +21,16 @@ use tui::Frame;
 pub struct WeightTable {
     state: TableState,
     items: Vec<Vec<String>>,
+    // where the tag weights came from, e.g. "weights.mon"
+    rule: String,
 }
 
 impl WeightTable {
-    pub fn new(weight_table_vec: Vec<Vec<String>>) -> Self {
+    pub fn new(weight_table_vec: Vec<Vec<String>>, rule: String) -> Self {
         Self {
             state: TableState::default(),
             items: weight_table_vec,
+            rule,
         }
     }
 
@@ -63,7 +74,8 @@ where
     let normal_style = Style::default().fg(Color::White);
     let widths = [
         Constraint::Percentage(20),
-        Constraint::Percentage(40),
+        Constraint::Percentage(35),
+        Constraint::Percentage(5),
         Constraint::Percentage(10),
         Constraint::Percentage(10),
         Constraint::Percentage(20),
@@ -79,16 +91,24 @@ where
     });
 
     // instantiate the table with the tasks provided in the task list
+    let title = format!("TAG WEIGHT TABLE (weights from {})", tagweight_table.rule);
     let table = Table::new(rows)
         .header(
-            Row::new(vec!["Tag", "Task", "TagProb", "DueProb", "TotalProb"])
-                .style(Style::default().add_modifier(Modifier::BOLD))
-                .bottom_margin(1),
+            Row::new(vec![
+                "Tag",
+                "Task",
+                "Prio",
+                "TagProb",
+                "DueProb",
+                "TotalProb",
+            ])
+            .style(Style::default().add_modifier(Modifier::BOLD))
+            .bottom_margin(1),
         )
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("TAG WEIGHT TABLE")
+                .title(title)
                 .border_type(BorderType::Rounded),
         )
         .highlight_style(selected_style)
@@ -97,6 +117,237 @@ where
     f.render_stateful_widget(table, area, &mut tagweight_table.state);
 }
 
+// how far away a task is due at each column of the urgency table
+const URGENCY_SAMPLES: [(&str, i64); 5] = [
+    ("1h", 60),
+    ("1d", 60 * 24),
+    ("1w", 60 * 24 * 7),
+    ("30d", 60 * 24 * 30),
+    ("1y", 60 * 24 * 365),
+];
+
+// draw the urgency model of each tag. the columns show how much
+// weight a task due that far away gets next to one due in an hour
+pub fn draw_urgency<B>(
+    f: &mut Frame<B>,
+    tags: &[String],
+    use_due_dates: &[bool],
+    urgency: &[UrgencyModel],
+    area: Rect,
+) where
+    B: Backend,
+{
+    let rows = tags.iter().enumerate().map(|(i_tags, tag)| {
+        let mut cells = vec![Cell::from(tag.clone())];
+        if use_due_dates[i_tags] {
+            let model = &urgency[i_tags];
+            let baseline = model.weight(URGENCY_SAMPLES[0].1);
+            cells.push(Cell::from(model.describe()));
+            cells.extend(URGENCY_SAMPLES.iter().map(|(_, minutes)| {
+                Cell::from(format!("{:.2}%", model.weight(*minutes) / baseline * 100.0))
+            }));
+        } else {
+            cells.push(Cell::from("uniform (use_due_dates off)"));
+        }
+        Row::new(cells)
+    });
+
+    let mut header = vec!["Tag", "Model"];
+    header.extend(URGENCY_SAMPLES.iter().map(|(label, _)| *label));
+    let widths = [
+        Constraint::Percentage(15),
+        Constraint::Percentage(35),
+        Constraint::Percentage(10),
+        Constraint::Percentage(10),
+        Constraint::Percentage(10),
+        Constraint::Percentage(10),
+        Constraint::Percentage(10),
+    ];
+    let table = Table::new(rows)
+        .header(Row::new(header).style(Style::default().add_modifier(Modifier::BOLD)))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("URGENCY (weight when due in ... vs due in 1h)")
+                .border_type(BorderType::Rounded),
+        )
+        .widths(&widths);
+    f.render_widget(table, area);
+}
+
+// draw the historical stats screen in the specified rectangle.
+// top row: pomodoros per tag and per day. middle row: weekday
+// heatmap and streaks. bottom row: configured weights against
+// the share of time actually spent on each tag.
+pub fn draw_history<B>(f: &mut Frame<B>, stats: &HistoryStats, area: Rect)
+where
+    B: Backend,
+{
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage(35),
+                Constraint::Percentage(30),
+                Constraint::Percentage(35),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+    let top_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(chunks[0]);
+    let middle_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(75), Constraint::Percentage(25)].as_ref())
+        .split(chunks[1]);
+
+    // pomodoros per tag
+    let bar_data: Vec<(&str, u64)> = stats
+        .tag_totals
+        .iter()
+        .map(|(tag, ctr)| (tag.as_str(), *ctr))
+        .collect();
+    let barchart = BarChart::default()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("POMODOROS PER TAG (LAST {} DAYS)", stats.days))
+                .border_type(BorderType::Rounded),
+        )
+        .data(&bar_data)
+        .bar_width(10)
+        .bar_gap(2)
+        .bar_style(Style::default().fg(Color::Yellow))
+        .value_style(Style::default().fg(Color::Black).bg(Color::Yellow));
+    f.render_widget(barchart, top_chunks[0]);
+
+    // pomodoros per day. each day is stretched to fill the block
+    let stretch =
+        (top_chunks[1].width.saturating_sub(2) as usize / stats.daily_totals.len()).max(1);
+    let daily_data: Vec<u64> = stats
+        .daily_totals
+        .iter()
+        .flat_map(|ctr| std::iter::repeat(*ctr).take(stretch))
+        .collect();
+    let sparkline = Sparkline::default()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("POMODOROS PER DAY (LAST {} DAYS)", stats.days))
+                .border_type(BorderType::Rounded),
+        )
+        .data(&daily_data)
+        .style(Style::default().fg(Color::Yellow));
+    f.render_widget(sparkline, top_chunks[1]);
+
+    // weekday heatmap. the brighter the cell the more was done
+    let heat_max = stats
+        .weekday_counts
+        .iter()
+        .flatten()
+        .max()
+        .copied()
+        .unwrap_or_default()
+        .max(1);
+    let heat_colors = [
+        Color::Reset,
+        Color::Indexed(22),
+        Color::Indexed(28),
+        Color::Indexed(34),
+        Color::Indexed(40),
+    ];
+    let heat_rows = stats.tags.iter().enumerate().map(|(i_tags, tag)| {
+        let mut cells = vec![Cell::from(tag.clone())];
+        for ctr in &stats.weekday_counts[i_tags] {
+            let shade = (*ctr * (heat_colors.len() as u64 - 1) + heat_max - 1) / heat_max;
+            cells.push(
+                Cell::from(ctr.to_string()).style(Style::default().bg(heat_colors[shade as usize])),
+            );
+        }
+        Row::new(cells)
+    });
+    let mut heat_header = vec!["Tag"];
+    heat_header.extend(WEEKDAYS.iter());
+    let heat_widths = [
+        Constraint::Percentage(20),
+        Constraint::Percentage(10),
+        Constraint::Percentage(10),
+        Constraint::Percentage(10),
+        Constraint::Percentage(10),
+        Constraint::Percentage(10),
+        Constraint::Percentage(10),
+        Constraint::Percentage(10),
+    ];
+    let heatmap = Table::new(heat_rows)
+        .header(
+            Row::new(heat_header)
+                .style(Style::default().add_modifier(Modifier::BOLD))
+                .bottom_margin(1),
+        )
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("WEEKDAY HEATMAP")
+                .border_type(BorderType::Rounded),
+        )
+        .widths(&heat_widths);
+    f.render_widget(heatmap, middle_chunks[0]);
+
+    // streaks
+    let streak_text = vec![
+        Spans::from(Span::raw(format!("current: {} days", stats.current_streak))),
+        Spans::from(Span::raw(format!("longest: {} days", stats.longest_streak))),
+    ];
+    let streaks = Paragraph::new(streak_text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("STREAKS")
+                .border_type(BorderType::Rounded),
+        )
+        .alignment(Alignment::Center);
+    f.render_widget(streaks, middle_chunks[1]);
+
+    // configured weight against actual share of time per tag
+    let share_rows = WEEKDAYS.iter().enumerate().map(|(weekday, day)| {
+        let mut cells = vec![Cell::from(*day)];
+        for (configured, actual) in &stats.weight_shares[weekday] {
+            cells.push(Cell::from(format!(
+                "{:.0}% / {:.0}%",
+                configured * 100.0,
+                actual * 100.0
+            )));
+        }
+        Row::new(cells)
+    });
+    let mut share_header = vec![String::from("Day")];
+    share_header.extend(stats.tags.iter().map(|tag| format!("{} (cfg/act)", tag)));
+    let share_widths: Vec<Constraint> = std::iter::once(Constraint::Length(5))
+        .chain(
+            stats
+                .tags
+                .iter()
+                .map(|_| Constraint::Percentage(90 / stats.tags.len().max(1) as u16)),
+        )
+        .collect();
+    let shares = Table::new(share_rows)
+        .header(
+            Row::new(share_header)
+                .style(Style::default().add_modifier(Modifier::BOLD))
+                .bottom_margin(1),
+        )
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("CONFIGURED WEIGHTS VS TIME ACTUALLY SPENT")
+                .border_type(BorderType::Rounded),
+        )
+        .widths(&share_widths);
+    f.render_widget(shares, chunks[2]);
+}
+
 // Help Table drawing functions
 pub struct HelpTable<'a> {
     state: TableState,
@@ -112,10 +363,21 @@ impl<'a> HelpTable<'a> {
                 vec!["j", "scroll down in ALL TASKS table"],
                 vec!["r", "reroll the given task without marking as complete"],
                 vec!["c", "complete the given task and select a new one"],
+                vec!["a", "add a new task"],
+                vec!["e", "edit the highlighted task"],
+                vec!["d", "delete the highlighted task"],
                 vec!["f", "fast forward current task bar to completion"],
                 vec!["0", "rewid current task bar back to 0"],
                 vec!["p", "toggle pause"],
+                vec!["m", "switch how the next task is picked"],
+                vec!["y", "explain why the current task was picked"],
+                vec![
+                    "enter",
+                    "work on the highlighted task. again to lock it in for another pomodoro",
+                ],
                 vec!["s", "access stats menu"],
+                vec!["g", "access history charts"],
+                vec!["w", "switch history charts between last 7 and 30 days"],
                 vec!["q", "quit rusty-krab-manager"],
                 vec!["h", "toggle help menu"],
             ],
@@ -190,12 +452,18 @@ where
 // and its variables
 pub struct App {
     state: TableState,
-    pub items: Vec<Vec<String>>,
+    pub items: Vec<Assignment>,
     // pub selected: usize,
+    pub timer: Timer,
     pub progress: f64,
     pub current_task: Vec<String>,
-    pub paused: bool,
     pub completed: Vec<(String, String)>,
+    // message shown with the timer, e.g. when saving a task failed
+    pub status: Option<String>,
+    // how the next task is picked
+    pub strategy: &'static str,
+    // pomodoros done on each task so far, by tag and task name
+    pub actuals: HashMap<(String, String), i64>,
 }
 
 impl App {
@@ -204,20 +472,23 @@ impl App {
     // values are actually used. Used for debugging.
     pub fn new() -> Self {
         Self {
-            items: vec![vec![
-                String::from("GANG"),
-                String::from("GANG"),
-                String::from("GANG"),
-            ]],
+            items: vec![Assignment {
+                name: String::from("GANG"),
+                tag: String::from("GANG"),
+                due_time: Some(String::from("GANG")),
+                priority: None,
+                estimate: None,
+                recurrence: None,
+            }],
             // selected: 0,
             state: TableState::default(),
+            timer: Timer::start(25),
             progress: 0.0,
             current_task: vec![
                 String::from("Hello\n"),
                 String::from("Heyyo!\n"),
                 String::from("MEMES\n"),
             ],
-            paused: false,
             completed: vec![
                 (String::from("GANG"), String::from("0")),
                 (String::from("GANG"), String::from("1")),
@@ -225,25 +496,35 @@ impl App {
                 (String::from("GANG"), String::from("3")),
                 (String::from("GANG"), String::from("4")),
             ],
+            status: None,
+            strategy: "weighted",
+            actuals: HashMap::new(),
         }
     }
 
+    // pomodoros done on the given task so far
+    pub fn done_on(&self, assign: &Assignment) -> i64 {
+        self.actuals
+            .get(&(assign.tag.clone(), assign.name.clone()))
+            .copied()
+            .unwrap_or(0)
+    }
+
     // Function to update the app.
-    // This runs every 250 milliseconds and returns
-    // true when the app hits 100%
-    pub fn update(&mut self, minutes: i64) -> bool {
-        self.progress += (250.0 / 60000.0) / minutes as f64;
-        if self.progress > 1.0 {
-            self.progress = 0.0;
-            return true;
-        }
-        false
+    // This runs on every tick and returns
+    // true when the timer hits 100%
+    pub fn update(&mut self) -> bool {
+        self.progress = self.timer.progress();
+        self.timer.is_done()
     }
 
     pub fn next(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
-                if i > self.items.len() - 1 {
+                if i >= self.items.len() - 1 {
                     0
                 } else {
                     i + 1
@@ -255,6 +536,9 @@ impl App {
     }
 
     pub fn previous(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i == 0 {
@@ -267,6 +551,21 @@ impl App {
         };
         self.state.select(Some(i));
     }
+
+    // the task highlighted in the ALL TASKS table
+    pub fn selected(&self) -> Option<&Assignment> {
+        self.state.selected().and_then(|i| self.items.get(i))
+    }
+
+    // swap in a new task list and keep the highlight on the table
+    pub fn set_items(&mut self, items: Vec<Assignment>) {
+        self.items = items;
+        match self.state.selected() {
+            Some(_) if self.items.is_empty() => self.state.select(None),
+            Some(i) if i >= self.items.len() => self.state.select(Some(self.items.len() - 1)),
+            _ => {}
+        }
+    }
 }
 
 // Draw the gauge used to showcase the remaining
@@ -278,12 +577,20 @@ where
     let gauge = Gauge::default()
         .block(
             Block::default()
-                .title("TIME LEFT")
+                .title(match &app.status {
+                    Some(status) => format!("TIME LEFT - {}", status),
+                    None => String::from("TIME LEFT"),
+                })
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded),
         )
         .gauge_style(Style::default().fg(Color::Yellow))
-        .ratio(app.progress);
+        .ratio(app.progress)
+        .label(format!(
+            "{:02}:{:02}",
+            app.timer.remaining().num_minutes(),
+            app.timer.remaining().num_seconds() % 60
+        ));
     f.render_widget(gauge, area);
 }
 
@@ -308,8 +615,10 @@ where
     let normal_style = Style::default().fg(Color::White);
     let widths = [
         Constraint::Percentage(20),
-        Constraint::Percentage(50),
-        Constraint::Percentage(30),
+        Constraint::Percentage(40),
+        Constraint::Percentage(5),
+        Constraint::Percentage(7),
+        Constraint::Percentage(28),
     ];
 
     // code snippet based on spotify-tui. essentially allows
@@ -322,24 +631,27 @@ where
     // }
     // });
     let rows = app.items.iter().map(|i| {
-        let cells = i.iter().map(|c| {
-            let x = c.clone();
-            Cell::from(x)
-        });
+        let cells = vec![
+            Cell::from(i.tag.clone()),
+            Cell::from(i.name.clone()),
+            Cell::from(i.priority.clone().unwrap_or_default()),
+            Cell::from(i.progress(app.done_on(i))),
+            Cell::from(i.due_label().to_string()),
+        ];
         Row::new(cells).style(normal_style)
     });
 
     // instantiate the table with the tasks provided in the task list
     let task_table = Table::new(rows)
         .header(
-            Row::new(vec!["Tag", "Name", "Due Date"])
+            Row::new(vec!["Tag", "Name", "Prio", "Pomos", "Due Date"])
                 .style(Style::default().add_modifier(Modifier::BOLD))
                 .bottom_margin(1),
         )
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("ALL TASKS")
+                .title(format!("ALL TASKS - picking: {}", app.strategy))
                 .border_type(BorderType::Rounded),
         )
         .highlight_symbol(">> ")
@@ -407,3 +719,282 @@ where
     );
     f.render_widget(task_ctr, area);
 }
+
+// the field of the task form being typed into
+#[derive(Clone, Copy, PartialEq)]
+enum FormField {
+    Tag,
+    Name,
+    Due,
+    Extras,
+}
+
+// What the main loop should do after the form saw a key
+pub enum FormAction {
+    Continue,
+    Cancel,
+    Submit(Assignment),
+}
+
+// Form used to add a task or edit the highlighted one
+pub struct TaskForm {
+    // the assignment being edited. None when adding a new one
+    pub editing: Option<Assignment>,
+    tags: Vec<String>,
+    tag_idx: usize,
+    name: String,
+    due: String,
+    // the optional fields after the due date, comma separated
+    extras: String,
+    field: FormField,
+    error: Option<String>,
+}
+
+impl TaskForm {
+    // empty form to add a new task. the tag picker only
+    // offers the tags in the config
+    pub fn add(tags: &[String]) -> Self {
+        Self {
+            editing: None,
+            tags: tags.to_vec(),
+            tag_idx: 0,
+            name: String::new(),
+            due: String::new(),
+            extras: String::new(),
+            field: FormField::Name,
+            error: None,
+        }
+    }
+
+    // form filled in with an existing task
+    pub fn edit(tags: &[String], assign: &Assignment) -> Self {
+        Self {
+            editing: Some(assign.clone()),
+            tag_idx: tags.iter().position(|tag| *tag == assign.tag).unwrap_or(0),
+            name: assign.name.clone(),
+            due: assign.due_time.clone().unwrap_or_default(),
+            extras: assign.extras().join(", "),
+            ..Self::add(tags)
+        }
+    }
+
+    pub fn handle_key(&mut self, key: Key) -> FormAction {
+        match key {
+            Key::Esc => return FormAction::Cancel,
+            Key::Char('\n') => match self.validate() {
+                Ok(assign) => return FormAction::Submit(assign),
+                Err(e) => self.error = Some(e),
+            },
+            Key::Char('\t') | Key::Down => {
+                self.field = match self.field {
+                    FormField::Tag => FormField::Name,
+                    FormField::Name => FormField::Due,
+                    FormField::Due => FormField::Extras,
+                    FormField::Extras => FormField::Tag,
+                }
+            }
+            Key::BackTab | Key::Up => {
+                self.field = match self.field {
+                    FormField::Tag => FormField::Extras,
+                    FormField::Name => FormField::Tag,
+                    FormField::Due => FormField::Name,
+                    FormField::Extras => FormField::Due,
+                }
+            }
+            Key::Left if self.field == FormField::Tag => {
+                self.tag_idx = (self.tag_idx + self.tags.len() - 1) % self.tags.len();
+            }
+            Key::Right | Key::Char(' ') if self.field == FormField::Tag => {
+                self.tag_idx = (self.tag_idx + 1) % self.tags.len();
+            }
+            Key::Char(c) => match self.field {
+                FormField::Name => self.name.push(c),
+                FormField::Due => self.due.push(c),
+                FormField::Extras => self.extras.push(c),
+                FormField::Tag => {}
+            },
+            Key::Backspace => match self.field {
+                FormField::Name => {
+                    self.name.pop();
+                }
+                FormField::Due => {
+                    self.due.pop();
+                }
+                FormField::Extras => {
+                    self.extras.pop();
+                }
+                FormField::Tag => {}
+            },
+            _ => {}
+        }
+        FormAction::Continue
+    }
+
+    // turn the form into an assignment if everything in it is valid
+    fn validate(&self) -> Result<Assignment, String> {
+        assignment_from_input(
+            &self.tags[self.tag_idx],
+            &self.name,
+            &self.due,
+            &self.extras,
+            Local::now(),
+        )
+        .map_err(|e| e.to_string())
+    }
+}
+
+// a rectangle of the given size in the middle of another one
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_y) / 2),
+                Constraint::Percentage(percent_y),
+                Constraint::Percentage((100 - percent_y) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(r);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_x) / 2),
+                Constraint::Percentage(percent_x),
+                Constraint::Percentage((100 - percent_x) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(popup_layout[1])[1]
+}
+
+// Draw the task form as a popup over the given area
+pub fn draw_task_form<B>(f: &mut Frame<B>, form: &TaskForm, area: Rect)
+where
+    B: Backend,
+{
+    let active_style = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+    let field_line = |label: &str, value: String, field: FormField| {
+        let style = if form.field == field {
+            active_style
+        } else {
+            Style::default()
+        };
+        Spans::from(vec![
+            Span::styled(format!("{:<8}", label), Style::default()),
+            Span::styled(value, style),
+        ])
+    };
+    let cursor = |field: FormField| if form.field == field { "_" } else { "" };
+
+    let mut text = vec![
+        field_line(
+            "Tag:",
+            format!("< {} >", form.tags[form.tag_idx]),
+            FormField::Tag,
+        ),
+        field_line(
+            "Name:",
+            format!("{}{}", form.name, cursor(FormField::Name)),
+            FormField::Name,
+        ),
+        field_line(
+            "Due:",
+            format!("{}{}", form.due, cursor(FormField::Due)),
+            FormField::Due,
+        ),
+        field_line(
+            "Extras:",
+            format!("{}{}", form.extras, cursor(FormField::Extras)),
+            FormField::Extras,
+        ),
+        Spans::from(Span::styled(
+            "due (optional): 2026-11-02 17:00, 2026-11-02, tomorrow 17:00, fri, +3d, next week",
+            Style::default().add_modifier(Modifier::DIM),
+        )),
+        Spans::from(Span::styled(
+            "extras (optional, comma separated): priority ! !! !!! or C B A, estimate ~3, repeat @weekly mon",
+            Style::default().add_modifier(Modifier::DIM),
+        )),
+        Spans::from(Span::raw("")),
+    ];
+    if let Some(error) = &form.error {
+        text.push(Spans::from(Span::styled(
+            error.clone(),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    text.push(Spans::from(Span::raw(
+        "tab: next field  ←/→: change tag  enter: save  esc: cancel",
+    )));
+
+    let title = if form.editing.is_some() {
+        "EDIT TASK"
+    } else {
+        "ADD TASK"
+    };
+    let popup = Paragraph::new(text)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded),
+        )
+        .wrap(Wrap { trim: false });
+    let popup_area = centered_rect(60, 40, area);
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup, popup_area);
+}
+
+// Ask for confirmation before a task is deleted
+pub fn draw_delete_confirm<B>(f: &mut Frame<B>, assign: &Assignment, area: Rect)
+where
+    B: Backend,
+{
+    let text = vec![
+        Spans::from(Span::raw(format!("Delete '{}'?", assign.name))),
+        Spans::from(Span::raw("")),
+        Spans::from(Span::raw("y: delete  any other key: cancel")),
+    ];
+    let popup = Paragraph::new(text)
+        .block(
+            Block::default()
+                .title("DELETE TASK")
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded),
+        )
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    let popup_area = centered_rect(50, 20, area);
+    f.render_widget(Clear, popup_area);
+    f.render_widget(popup, popup_area);
+}
+
+// Explain why the current task came up
+pub fn draw_why<B>(f: &mut Frame<B>, rows: &[(String, String)], area: Rect)
+where
+    B: Backend,
+{
+    let rows = rows.iter().map(|(label, value)| {
+        Row::new(vec![
+            Cell::from(label.clone()).style(Style::default().add_modifier(Modifier::BOLD)),
+            Cell::from(value.clone()),
+        ])
+    });
+    let widths = [Constraint::Percentage(30), Constraint::Percentage(70)];
+    let table = Table::new(rows)
+        .block(
+            Block::default()
+                .title("WHY THIS TASK? (any key closes)")
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded),
+        )
+        .widths(&widths)
+        .column_spacing(1);
+    let popup_area = centered_rect(80, 50, area);
+    f.render_widget(Clear, popup_area);
+    f.render_widget(table, popup_area);
+}
@@ -0,0 +1,271 @@
+use super::errors::{collect_problems, RkmError};
+use super::rand_utils::normalize;
+use config::Value;
+use std::collections::HashMap;
+
+// THESE ARE ALL FUNCTIONS RELATED TO HOW MUCH
+// MORE LIKELY A TASK GETS AS ITS DUE DATE NEARS
+//
+
+// the names models go by in the [urgency] table
+pub const MODEL_NAMES: [&str; 4] = ["inverse", "exponential", "linear", "hard"];
+
+// How the weight of a task grows as it comes due
+#[derive(Clone, PartialEq)]
+pub enum UrgencyModel {
+    // weight is 1 / minutes left. the default
+    Inverse,
+    // weight halves every half_life_hours further away
+    Exponential { half_life_hours: f64 },
+    // weight 1 beyond the horizon, ramping up to peak at the due date
+    Linear { horizon_hours: f64, peak: f64 },
+    // tasks due within the window take all the weight.
+    // otherwise the same as inverse
+    Hard { within_hours: f64 },
+}
+
+impl Default for UrgencyModel {
+    fn default() -> Self {
+        UrgencyModel::Inverse
+    }
+}
+
+impl UrgencyModel {
+    // weight of a task due in the given number of minutes, before
+    // it is turned into a probability. a task due now (or overdue)
+    // counts as due in one minute so nothing divides by zero
+    pub fn weight(&self, minutes_left: i64) -> f64 {
+        let minutes = minutes_left.max(1) as f64;
+        let hours = minutes / 60.0;
+        match self {
+            UrgencyModel::Inverse | UrgencyModel::Hard { .. } => 1.0 / minutes,
+            UrgencyModel::Exponential { half_life_hours } => 0.5_f64.powf(hours / half_life_hours),
+            UrgencyModel::Linear {
+                horizon_hours,
+                peak,
+            } => 1.0 + (peak - 1.0) * (1.0 - hours / horizon_hours).max(0.0),
+        }
+    }
+
+    // Turn the minutes until each task is due into a probability
+    // distribution. If every weight is too small to add up (e.g.
+    // everything is many half lives away) all tasks are equally likely
+    pub fn pdf(&self, minutes_left: &[i64]) -> Vec<f64> {
+        let mut weights: Vec<f64> = minutes_left.iter().map(|m| self.weight(*m)).collect();
+        if let UrgencyModel::Hard { within_hours } = self {
+            let within = (within_hours * 60.0) as i64;
+            if minutes_left.iter().any(|m| *m <= within) {
+                for (weight, m) in weights.iter_mut().zip(minutes_left) {
+                    if *m > within {
+                        *weight = 0.0;
+                    }
+                }
+            }
+        }
+
+        normalize(weights)
+    }
+
+    // the model and its parameters as shown on the stats screen
+    pub fn describe(&self) -> String {
+        match self {
+            UrgencyModel::Inverse => String::from("inverse"),
+            UrgencyModel::Exponential { half_life_hours } => {
+                format!("exponential, half-life {}h", half_life_hours)
+            }
+            UrgencyModel::Linear {
+                horizon_hours,
+                peak,
+            } => format!("linear, horizon {}h, peak {}x", horizon_hours, peak),
+            UrgencyModel::Hard { within_hours } => {
+                format!("hard, due within {}h always wins", within_hours)
+            }
+        }
+    }
+}
+
+// read a positive number out of a tag's urgency table
+fn positive_param(
+    tag: &str,
+    params: &HashMap<String, Value>,
+    key: &str,
+    default: Option<f64>,
+) -> Result<f64, RkmError> {
+    let bad_urgency = |reason: String| RkmError::BadUrgency {
+        tag: tag.to_string(),
+        reason,
+    };
+    let value = match (params.get(key), default) {
+        (Some(value), _) => value
+            .clone()
+            .into_float()
+            .map_err(|_| bad_urgency(format!("{} is not a number", key)))?,
+        (None, Some(default)) => default,
+        (None, None) => return Err(bad_urgency(format!("{} is missing", key))),
+    };
+    if value > 0.0 {
+        Ok(value)
+    } else {
+        Err(bad_urgency(format!("{} has to be above 0", key)))
+    }
+}
+
+// Read the [urgency] table of the config into one model per
+// tag, in the order of the tags, with a problem for every tag
+// that can not be read. Tags not in the table use the inverse model.
+pub fn parse_urgency(
+    table: HashMap<String, Value>,
+    tags: &[String],
+) -> Result<Vec<UrgencyModel>, Vec<RkmError>> {
+    // in a fixed order so problems are always listed the same way
+    let mut entries: Vec<(String, Value)> = table.into_iter().collect();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let parsed = collect_problems(
+        entries
+            .into_iter()
+            .map(|(tag, value)| parse_tag_urgency(tag, value, tags)),
+    )?;
+
+    let mut models = vec![UrgencyModel::default(); tags.len()];
+    for (tag_idx, model) in parsed {
+        models[tag_idx] = model;
+    }
+    Ok(models)
+}
+
+// the model of one tag of the [urgency] table and which tag it is
+fn parse_tag_urgency(
+    tag: String,
+    value: Value,
+    tags: &[String],
+) -> Result<(usize, UrgencyModel), RkmError> {
+    let bad_urgency = |reason: &str| RkmError::BadUrgency {
+        tag: tag.clone(),
+        reason: reason.to_string(),
+    };
+    let tag_idx = tags
+        .iter()
+        .position(|known| *known == tag)
+        .ok_or_else(|| bad_urgency("not one of the tags"))?;
+    let params = value
+        .into_table()
+        .map_err(|_| bad_urgency("expected a table like { model = \"inverse\" }"))?;
+    let model = params
+        .get("model")
+        .cloned()
+        .ok_or_else(|| bad_urgency("model is missing"))?
+        .into_string()
+        .map_err(|_| bad_urgency("model is not a string"))?;
+
+    let model = match model.as_str() {
+        "inverse" => UrgencyModel::Inverse,
+        "exponential" => UrgencyModel::Exponential {
+            half_life_hours: positive_param(&tag, &params, "half_life_hours", None)?,
+        },
+        "linear" => UrgencyModel::Linear {
+            horizon_hours: positive_param(&tag, &params, "horizon_hours", None)?,
+            peak: positive_param(&tag, &params, "peak", Some(10.0))?,
+        },
+        "hard" => UrgencyModel::Hard {
+            within_hours: positive_param(&tag, &params, "within_hours", None)?,
+        },
+        _ => {
+            return Err(bad_urgency(&format!(
+                "unknown model '{}'. expected one of: {}",
+                model,
+                MODEL_NAMES.join(", ")
+            )))
+        }
+    };
+    Ok((tag_idx, model))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(found: &[f64], expected: &[f64]) {
+        assert_eq!(found.len(), expected.len());
+        for (found, expected) in found.iter().zip(expected) {
+            assert!(
+                (found - expected).abs() < 1e-9,
+                "{:?} != {:?}",
+                found,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn weights_of_each_model() {
+        let inverse = UrgencyModel::Inverse;
+        assert_close(
+            &[inverse.weight(30), inverse.weight(0), inverse.weight(-5)],
+            &[1.0 / 30.0, 1.0, 1.0],
+        );
+
+        let exponential = UrgencyModel::Exponential {
+            half_life_hours: 2.0,
+        };
+        assert_close(
+            &[exponential.weight(120), exponential.weight(240)],
+            &[0.5, 0.25],
+        );
+
+        let linear = UrgencyModel::Linear {
+            horizon_hours: 10.0,
+            peak: 10.0,
+        };
+        assert_close(
+            &[linear.weight(300), linear.weight(600), linear.weight(6000)],
+            &[5.5, 1.0, 1.0],
+        );
+    }
+
+    #[test]
+    fn pdfs_sum_to_one() {
+        assert_close(
+            &UrgencyModel::Inverse.pdf(&[60, 120]),
+            &[2.0 / 3.0, 1.0 / 3.0],
+        );
+
+        // everything many half lives away is equally likely
+        let exponential = UrgencyModel::Exponential {
+            half_life_hours: 1.0,
+        };
+        assert_close(&exponential.pdf(&[1_000_000, 2_000_000]), &[0.5, 0.5]);
+    }
+
+    #[test]
+    fn hard_deadlines_take_all_the_weight() {
+        let hard = UrgencyModel::Hard { within_hours: 1.0 };
+        assert_close(&hard.pdf(&[30, 600, 6000]), &[1.0, 0.0, 0.0]);
+        // nothing within the window falls back to inverse
+        assert_close(&hard.pdf(&[120, 240]), &[2.0 / 3.0, 1.0 / 3.0]);
+    }
+
+    #[test]
+    fn every_bad_tag_is_reported() {
+        let tags = vec![String::from("school"), String::from("job")];
+        let mut table = HashMap::new();
+        let model = |name: &str| {
+            let mut params = HashMap::new();
+            params.insert(String::from("model"), Value::from(name));
+            Value::from(params)
+        };
+        table.insert(String::from("job"), model("cubic"));
+        table.insert(String::from("gym"), model("inverse"));
+        table.insert(String::from("school"), model("hard"));
+        assert_eq!(parse_urgency(table, &tags).err().map(|e| e.len()), Some(3));
+
+        // a model missing its parameter
+        let mut table = HashMap::new();
+        table.insert(String::from("job"), model("exponential"));
+        assert!(parse_urgency(table, &tags).is_err());
+
+        let mut table = HashMap::new();
+        table.insert(String::from("job"), model("inverse"));
+        let models = parse_urgency(table, &tags).unwrap();
+        assert!(models == vec![UrgencyModel::Inverse; 2]);
+    }
+}
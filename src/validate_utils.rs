@@ -0,0 +1,53 @@
+use super::assignment_utils::{readin_tasks, task_problems};
+use super::errors::RkmError;
+use super::settings_util::{config_problems, parse_all_settings, WEEKDAYS};
+use chrono::Local;
+
+// THESE ARE ALL FUNCTIONS RELATED TO THE 'check'
+// SUBCOMMAND
+//
+
+// Check the config and the task file it points to and print
+// every problem found. Returns whether rkm would run.
+pub fn run_check(config_path: &str) -> bool {
+    println!("checking {}", config_path);
+    let mut errors = vec![];
+    let mut warnings = vec![];
+
+    match parse_all_settings(config_path) {
+        // without a config there are no tags to check tasks against
+        Err(problems) => errors.extend(problems),
+        Ok(config) => {
+            let all_days: Vec<usize> = (0..WEEKDAYS.len()).collect();
+            errors.extend(config_problems(&config, &all_days));
+
+            if config.task_path.exists() {
+                match task_problems(&config.task_path, &config.tags) {
+                    Ok((task_errors, ignored)) => {
+                        // nothing left to roll is only worth a mention
+                        // if every line is fine on its own
+                        if task_errors.is_empty() {
+                            if let Err(e @ RkmError::EmptyTaskList(_)) =
+                                readin_tasks(&config.task_path, &config.tags, Local::now())
+                            {
+                                errors.push(e);
+                            }
+                        }
+                        errors.extend(task_errors);
+                        warnings.extend(ignored);
+                    }
+                    Err(e) => errors.push(e),
+                }
+            }
+        }
+    }
+
+    for e in &errors {
+        println!("error: {}", e);
+    }
+    for w in &warnings {
+        println!("warning: {}", w);
+    }
+    println!("{} errors, {} warnings", errors.len(), warnings.len());
+    errors.is_empty()
+}